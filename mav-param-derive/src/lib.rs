@@ -1,16 +1,53 @@
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::{
-    parse_macro_input, Attribute, Data, DeriveInput, Fields, FieldsNamed, Ident, Lit, MetaNameValue,
+    parse_macro_input, Attribute, Data, DataEnum, DeriveInput, Field, Fields, Ident, Index, Lit,
+    LitStr, Member, MetaNameValue,
 };
 
-/// Derives the `Tree` trait for a struct.
+/// Derives the `Tree` trait for a struct or an enum.
 ///
 /// This macro automatically implements the `Tree` trait for a struct, allowing it
 /// to be part of a parameter hierarchy. Each field of the struct becomes an entry
-/// in the parameter tree.
+/// in the parameter tree. The fields of tuple structs are named by their position,
+/// like `"0"`, `"1"`, and so on.
 ///
 /// Use the `#[tree(rename = "name")]` attribute to customize field names in the tree.
+///
+/// Enums with fields are exposed as a `"tag"` parameter holding the index of the active
+/// variant as a `U8`, followed by the fields of the active variant, so the parameter count
+/// depends on the active variant. The fields of struct variants keep their names, the field
+/// of a single-field tuple variant is named after the variant in lowercase, and the fields
+/// of other tuple variants are named by their position. Writing the tag switches to the
+/// variant with that index, whose fields are all set to their default, while writing the
+/// index of the active variant changes nothing. Use `#[tree(tag = "name")]` to rename the tag.
+///
+/// Use `#[tree(all_variants)]` on the enum to also allow wrapping it in `mav_param::Variants`,
+/// which holds the fields of every variant, and lists all of them after the tag. This requires
+/// all fields to implement `Default` and `Clone`, and to have unique names.
+///
+/// Example:
+/// ```ignore
+/// #[derive(Tree, Default, Clone)]
+/// struct EkfConfig {
+///     q: f32,
+///     r: f32,
+/// }
+///
+/// #[derive(Tree)]
+/// #[tree(all_variants)]
+/// enum Estimator {
+///     Ekf(EkfConfig),
+///     Complementary { gain: f32 },
+///     Disabled,
+/// }
+///
+/// #[derive(Tree)]
+/// struct Params {
+///     // Lists `est.tag`, `est.ekf.q`, `est.ekf.r` and `est.gain`
+///     est: mav_param::Variants<Estimator>,
+/// }
+/// ```
 #[proc_macro_derive(Tree, attributes(tree))]
 pub fn tree_derive(input: TokenStream) -> TokenStream {
     // Parse the input tokens into a syntax tree
@@ -21,33 +58,39 @@ pub fn tree_derive(input: TokenStream) -> TokenStream {
     let implementation = match &input.data {
         Data::Struct(data_struct) => match &data_struct.fields {
             // Handle normal structs with named fields
-            Fields::Named(fields_named) => generate_named_fields_impl(name, fields_named),
+            Fields::Named(fields_named) => generate_fields_impl(name, &fields_named.named),
+
+            // Handle tuple structs, whose fields are named by position
+            Fields::Unnamed(fields_unnamed) => generate_fields_impl(name, &fields_unnamed.unnamed),
 
-            _ => panic!("Tree derive only supports structs with named fields"),
+            Fields::Unit => panic!("Tree derive does not support unit structs"),
         },
-        _ => panic!("Tree derive only supports structs"),
+        Data::Enum(data_enum) => generate_enum_tree_impl(&input, data_enum),
+        Data::Union(_) => panic!("Tree derive only supports structs and enums"),
     };
 
     // Return the generated code
     TokenStream::from(implementation)
 }
 
-// Generate implementation for struct with named fields
-fn generate_named_fields_impl(
+// Generate implementation for struct with named or positional fields
+fn generate_fields_impl<'f>(
     name: &Ident,
-    fields_named: &FieldsNamed,
+    fields: impl IntoIterator<Item = &'f Field>,
 ) -> proc_macro2::TokenStream {
     // Collect field processing info
-    let field_info = fields_named
-        .named
-        .iter()
-        .map(|field| {
-            let field_name = field.ident.as_ref().unwrap();
-            let field_name_str = field_name.to_string();
+    let field_info = fields
+        .into_iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let (field_name, field_name_str) = match &field.ident {
+                Some(ident) => (Member::Named(ident.clone()), ident.to_string()),
+                None => (Member::Unnamed(Index::from(index)), index.to_string()),
+            };
 
             // Check for rename attribute
             let renamed = find_rename_attr(&field.attrs);
-            let param_name = renamed.unwrap_or_else(|| field_name_str.clone());
+            let param_name = renamed.unwrap_or(field_name_str);
 
             (field_name, param_name)
         })
@@ -67,6 +110,20 @@ fn generate_named_fields_impl(
         }
     });
 
+    // Generate match arms for get_ref_at
+    let get_ref_at_arms = field_info.iter().enumerate().map(|(index, (field_name, _))| {
+        quote! {
+            #index => Some(self.#field_name.node_ref()),
+        }
+    });
+
+    // Generate match arms for get_mut_at
+    let get_mut_at_arms = field_info.iter().enumerate().map(|(index, (field_name, _))| {
+        quote! {
+            #index => Some(self.#field_name.node_mut()),
+        }
+    });
+
     // Generate entries list
     let entry_strings = field_info.iter().map(|(_, param_name)| {
         quote! { #param_name }
@@ -90,6 +147,22 @@ fn generate_named_fields_impl(
                 }
             }
 
+            fn get_ref_at(&self, index: usize) -> Option<mav_param::NodeRef<'_>> {
+                use mav_param::Node;
+                match index {
+                    #(#get_ref_at_arms)*
+                    _ => None,
+                }
+            }
+
+            fn get_mut_at(&mut self, index: usize) -> Option<mav_param::NodeMut<'_>> {
+                use mav_param::Node;
+                match index {
+                    #(#get_mut_at_arms)*
+                    _ => None,
+                }
+            }
+
             fn entries(&self) -> &'static [&'static str] {
                 &[#(#entry_strings),*]
             }
@@ -97,6 +170,312 @@ fn generate_named_fields_impl(
     }
 }
 
+// Generate implementation for an enum, exposed as a tag and the fields of the active variant
+fn generate_enum_tree_impl(input: &DeriveInput, data_enum: &DataEnum) -> proc_macro2::TokenStream {
+    let name = &input.ident;
+    let (tag, all_variants) = find_enum_attrs(&input.attrs);
+
+    if data_enum.variants.len() > 256 {
+        panic!("Tree derive on enums supports at most 256 variants");
+    }
+
+    // Collect the fields of each variant, bound as `__field0`, `__field1` and so on
+    let variants = data_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            let single = variant.fields.len() == 1;
+            let fields = variant
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(index, field)| {
+                    let (member, member_str) = match &field.ident {
+                        Some(ident) => (Member::Named(ident.clone()), ident.to_string()),
+                        None if single => (
+                            Member::Unnamed(Index::from(index)),
+                            variant.ident.to_string().to_lowercase(),
+                        ),
+                        None => (Member::Unnamed(Index::from(index)), index.to_string()),
+                    };
+                    let param_name = find_rename_attr(&field.attrs).unwrap_or(member_str);
+                    let binding = format_ident!("__field{index}");
+                    (member, param_name, binding, &field.ty)
+                })
+                .collect::<Vec<_>>();
+            (&variant.ident, fields)
+        })
+        .collect::<Vec<_>>();
+
+    // Patterns binding all fields, and expressions creating a variant from defaults
+    let patterns = variants.iter().map(|(ident, fields)| {
+        let members = fields.iter().map(|(member, ..)| member);
+        let bindings = fields.iter().map(|(_, _, binding, _)| binding);
+        quote! { Self::#ident { #(#members: #bindings),* } }
+    });
+    let patterns = patterns.collect::<Vec<_>>();
+
+    let wildcards = variants
+        .iter()
+        .map(|(ident, _)| quote! { Self::#ident { .. } });
+    let wildcards = wildcards.collect::<Vec<_>>();
+
+    let defaults = variants.iter().map(|(ident, fields)| {
+        let members = fields.iter().map(|(member, ..)| member);
+        quote! { Self::#ident { #(#members: Default::default()),* } }
+    });
+
+    let indices = (0..variants.len())
+        .map(|index| index as u8)
+        .collect::<Vec<_>>();
+
+    let entries = variants.iter().map(|(_, fields)| {
+        let names = fields.iter().map(|(_, param_name, ..)| param_name);
+        quote! { &[#tag, #(#names),*] }
+    });
+
+    let ref_arms = variants.iter().map(|(_, fields)| {
+        let arms = fields
+            .iter()
+            .enumerate()
+            .map(|(index, (_, _, binding, _))| {
+                let index = index + 1;
+                quote! { #index => Some(#binding.node_ref()), }
+            });
+        quote! { match index { #(#arms)* _ => None } }
+    });
+    let ref_arms = ref_arms.collect::<Vec<_>>();
+
+    let mut_arms = variants.iter().map(|(_, fields)| {
+        let arms = fields
+            .iter()
+            .enumerate()
+            .map(|(index, (_, _, binding, _))| {
+                let index = index + 1;
+                quote! { #index => Some(#binding.node_mut()), }
+            });
+        quote! { match index { #(#arms)* _ => None } }
+    });
+
+    let store = if all_variants {
+        generate_variants_store(input, &tag, &variants)
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        impl mav_param::Tree for #name {
+            fn get_ref<'a>(&'a self, node: &str) -> Option<mav_param::NodeRef<'a>> {
+                let index = self.entries().iter().position(|entry| *entry == node)?;
+                self.get_ref_at(index)
+            }
+
+            fn get_mut<'a>(&'a mut self, node: &str) -> Option<mav_param::NodeMut<'a>> {
+                let index = self.entries().iter().position(|entry| *entry == node)?;
+                self.get_mut_at(index)
+            }
+
+            fn get_ref_at(&self, index: usize) -> Option<mav_param::NodeRef<'_>> {
+                use mav_param::Node;
+                if index == 0 {
+                    return Some(mav_param::NodeRef::Value(mav_param::Validated::value(self)));
+                }
+                match self { #( #patterns => #ref_arms, )* }
+            }
+
+            fn get_mut_at(&mut self, index: usize) -> Option<mav_param::NodeMut<'_>> {
+                use mav_param::Node;
+                if index == 0 {
+                    return Some(mav_param::NodeMut::Value(mav_param::ValueMut::Validated(self)));
+                }
+                match self { #( #patterns => #mut_arms, )* }
+            }
+
+            fn entries(&self) -> &'static [&'static str] {
+                match self { #( #wildcards => #entries, )* }
+            }
+        }
+
+        // The tag, which switches to the default of another variant when changed
+        impl mav_param::Validated for #name {
+            fn value(&self) -> mav_param::Value {
+                mav_param::Value::U8(match self {
+                    #( #wildcards => #indices, )*
+                })
+            }
+
+            fn assign(&mut self, value: mav_param::Value) -> Result<(), mav_param::Error> {
+                let mav_param::Value::U8(raw) = value else {
+                    return Err(mav_param::Error::TypeMismatch {
+                        expected: mav_param::ParamType::Uint8,
+                        received: value.param_type(),
+                    });
+                };
+
+                if mav_param::Validated::value(self) != value {
+                    *self = match raw {
+                        #( #indices => #defaults, )*
+                        _ => return Err(mav_param::Error::InvalidValue),
+                    };
+                }
+                Ok(())
+            }
+
+            fn selects_variant(&self) -> bool {
+                true
+            }
+        }
+
+        #store
+    }
+}
+
+// Generate the store holding the fields of every variant, for `mav_param::Variants`
+fn generate_variants_store(
+    input: &DeriveInput,
+    tag: &str,
+    variants: &[(&Ident, Vec<(Member, String, Ident, &syn::Type)>)],
+) -> proc_macro2::TokenStream {
+    let name = &input.ident;
+    let vis = &input.vis;
+    let store = format_ident!("__{name}Variants");
+    let count = variants.len();
+
+    // All fields are listed after the tag, so their names must be unique
+    let mut names = vec![tag.to_string()];
+    for (_, fields) in variants {
+        for (_, param_name, ..) in fields {
+            if names.contains(param_name) {
+                panic!(
+                    "Tree derive on enums with #[tree(all_variants)] requires unique field \
+                     names, rename `{param_name}` with #[tree(rename = \"..\")]"
+                );
+            }
+            names.push(param_name.clone());
+        }
+    }
+
+    // The fields of all variants are stored as `__value0`, `__value1` and so on
+    let mut offset = 0;
+    let stored = variants.iter().map(|(_, fields)| {
+        let values = (offset..offset + fields.len())
+            .map(|index| format_ident!("__value{index}"))
+            .collect::<Vec<_>>();
+        offset += fields.len();
+        values
+    });
+    let stored = stored.collect::<Vec<_>>();
+    let values = stored.iter().flatten().collect::<Vec<_>>();
+    let types = variants
+        .iter()
+        .flat_map(|(_, fields)| fields.iter().map(|(.., ty)| ty));
+    let positions = (1..=values.len()).collect::<Vec<_>>();
+
+    let mut stores = Vec::new();
+    let mut loads = Vec::new();
+    for (index, ((ident, fields), values)) in variants.iter().zip(&stored).enumerate() {
+        let members = fields.iter().map(|(member, ..)| member).collect::<Vec<_>>();
+        let bindings = fields.iter().map(|(_, _, binding, _)| binding);
+        let bindings = bindings.collect::<Vec<_>>();
+        let tag_index = index as u8;
+        stores.push(quote! {
+            Self::#ident { #(#members: #bindings),* } => {
+                store.__tag = mav_param::tree_impls::variants::VariantTag(#tag_index);
+                #( store.#values = #bindings; )*
+            }
+        });
+
+        // The last variant is loaded for any other index, which the tag does not accept
+        let pattern = if index + 1 == count {
+            quote! { _ }
+        } else {
+            quote! { #tag_index }
+        };
+        loads.push(quote! {
+            #pattern => Self::#ident { #(#members: store.#values.clone()),* },
+        });
+    }
+
+    quote! {
+        #[doc(hidden)]
+        #[derive(Default, Clone)]
+        #vis struct #store {
+            __tag: mav_param::tree_impls::variants::VariantTag<#count>,
+            #( #values: #types, )*
+        }
+
+        impl mav_param::Tree for #store {
+            fn get_ref<'a>(&'a self, node: &str) -> Option<mav_param::NodeRef<'a>> {
+                let index = self.entries().iter().position(|entry| *entry == node)?;
+                self.get_ref_at(index)
+            }
+
+            fn get_mut<'a>(&'a mut self, node: &str) -> Option<mav_param::NodeMut<'a>> {
+                let index = self.entries().iter().position(|entry| *entry == node)?;
+                self.get_mut_at(index)
+            }
+
+            fn get_ref_at(&self, index: usize) -> Option<mav_param::NodeRef<'_>> {
+                use mav_param::Node;
+                match index {
+                    0 => Some(self.__tag.node_ref()),
+                    #( #positions => Some(self.#values.node_ref()), )*
+                    _ => None,
+                }
+            }
+
+            fn get_mut_at(&mut self, index: usize) -> Option<mav_param::NodeMut<'_>> {
+                use mav_param::Node;
+                match index {
+                    0 => Some(self.__tag.node_mut()),
+                    #( #positions => Some(self.#values.node_mut()), )*
+                    _ => None,
+                }
+            }
+
+            fn entries(&self) -> &'static [&'static str] {
+                &[#(#names),*]
+            }
+        }
+
+        impl mav_param::AllVariants for #name {
+            type Store = #store;
+
+            fn store(self, store: &mut Self::Store) {
+                match self {
+                    #( #stores )*
+                }
+            }
+
+            fn load(store: &Self::Store) -> Self {
+                match store.__tag.0 {
+                    #( #loads )*
+                }
+            }
+        }
+    }
+}
+
+// Find the tag name and whether the fields of all variants can be stored
+fn find_enum_attrs(attrs: &[Attribute]) -> (String, bool) {
+    let mut tag = String::from("tag");
+    let mut all_variants = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("tree")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("tag") {
+                tag = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("all_variants") {
+                all_variants = true;
+            } else {
+                return Err(meta.error("unsupported tree attribute"));
+            }
+            Ok(())
+        })
+        .expect("invalid #[tree(..)] attribute");
+    }
+    (tag, all_variants)
+}
+
 // Updated function to extract rename attribute using syn 2.0 API
 fn find_rename_attr(attrs: &[Attribute]) -> Option<String> {
     for attr in attrs {
@@ -121,15 +500,27 @@ fn find_rename_attr(attrs: &[Attribute]) -> Option<String> {
     None
 }
 
-/// Derives the `Node` trait for a newtype struct.
+/// Derives the `Node` trait for a newtype struct or a fieldless enum.
+///
+/// For newtype structs (structs with a single unnamed field), this macro generates an
+/// implementation that delegates to the inner type's `Node` implementation.
 ///
-/// This macro generates an implementation that delegates to the inner type's `Node`
-/// implementation. It only works on newtype structs (structs with a single unnamed field).
+/// For enums without fields, the discriminant is exposed as an integer parameter of the
+/// type given by the `#[repr(..)]` attribute, and the enum implements `Validated`, so
+/// that writing a value which is not a discriminant is rejected. A 64-bit
+/// `#[repr(..)]` requires the `ext` feature of `mav-param`.
 ///
 /// Example:
 /// ```
 /// #[derive(Node)]
 /// struct MyWrapper(u8);
+///
+/// #[derive(Node)]
+/// #[repr(u8)]
+/// enum Mode {
+///     Manual = 0,
+///     Auto = 4,
+/// }
 /// ```
 #[proc_macro_derive(Node)]
 pub fn node_derive(input: TokenStream) -> TokenStream {
@@ -144,7 +535,8 @@ pub fn node_derive(input: TokenStream) -> TokenStream {
             }
             _ => panic!("Node derive only works on newtype structs with a single field"),
         },
-        _ => panic!("Node derive only works on structs"),
+        Data::Enum(data_enum) => return generate_enum_node_impl(&input, data_enum).into(),
+        Data::Union(_) => panic!("Node derive only works on structs and enums"),
     };
 
     // Delegate to the Node trait implementation of the inner type
@@ -161,3 +553,102 @@ pub fn node_derive(input: TokenStream) -> TokenStream {
     }
     .into()
 }
+
+// Generate implementation for a fieldless enum, exposed as its discriminant
+fn generate_enum_node_impl(input: &DeriveInput, data_enum: &DataEnum) -> proc_macro2::TokenStream {
+    let name = &input.ident;
+
+    let repr = find_repr_attr(&input.attrs)
+        .expect("Node derive on enums requires an integer #[repr(..)] attribute");
+
+    let variant = match repr.to_string().as_str() {
+        "u8" => quote! { U8 },
+        "i8" => quote! { I8 },
+        "u16" => quote! { U16 },
+        "i16" => quote! { I16 },
+        "u32" => quote! { U32 },
+        "i32" => quote! { I32 },
+        "u64" => quote! { U64 },
+        "i64" => quote! { I64 },
+        _ => panic!("Node derive on enums does not support #[repr({repr})]"),
+    };
+
+    let variants = data_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            if !matches!(variant.fields, Fields::Unit) {
+                panic!("Node derive only works on enums without fields");
+            }
+            &variant.ident
+        })
+        .collect::<Vec<_>>();
+
+    let implementation = quote! {
+        impl mav_param::Validated for #name {
+            fn value(&self) -> mav_param::Value {
+                mav_param::Value::#variant(match self {
+                    #( Self::#variants => Self::#variants as #repr, )*
+                })
+            }
+
+            fn assign(&mut self, value: mav_param::Value) -> Result<(), mav_param::Error> {
+                let mav_param::Value::#variant(raw) = value else {
+                    return Err(mav_param::Error::TypeMismatch {
+                        expected: mav_param::Validated::value(self).param_type(),
+                        received: value.param_type(),
+                    });
+                };
+
+                *self = match raw {
+                    #( raw if raw == Self::#variants as #repr => Self::#variants, )*
+                    _ => return Err(mav_param::Error::InvalidValue),
+                };
+                Ok(())
+            }
+        }
+
+        impl mav_param::Node for #name {
+            fn node_ref(&self) -> mav_param::NodeRef<'_> {
+                mav_param::NodeRef::Value(mav_param::Validated::value(self))
+            }
+
+            fn node_mut(&mut self) -> mav_param::NodeMut<'_> {
+                mav_param::NodeMut::Value(mav_param::ValueMut::Validated(self))
+            }
+        }
+    };
+
+    // The 64-bit values only exist with the `ext` feature, which the derive can't check
+    if matches!(repr.to_string().as_str(), "u64" | "i64") {
+        quote! { mav_param::__require_ext! { #implementation } }
+    } else {
+        implementation
+    }
+}
+
+// Find the integer type of the #[repr(..)] attribute, ignoring e.g. `C` or `align(..)`
+fn find_repr_attr(attrs: &[Attribute]) -> Option<Ident> {
+    const INTEGERS: [&str; 8] = ["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64"];
+
+    let mut repr = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            // Skip the arguments of e.g. `align(8)` or `packed(2)`
+            if meta.input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<proc_macro2::TokenStream>()?;
+            }
+
+            if let Some(ident) = meta.path.get_ident() {
+                if INTEGERS.iter().any(|integer| ident == integer) {
+                    repr = Some(ident.clone());
+                }
+            }
+            Ok(())
+        })
+        .expect("invalid #[repr(..)] attribute");
+    }
+    repr
+}
@@ -0,0 +1,612 @@
+use crate::server::{ParamServer, Request, Response};
+use crate::value::Primitive;
+use crate::{Error, Ident, Parameter, Tree, Value};
+
+/// The length of the value payload in the extended parameter protocol
+pub const EXT_VALUE_LEN: usize = 128;
+
+/// The type of a parameter in the extended protocol, corresponds to `MAV_PARAM_EXT_TYPE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum ParamExtType {
+    Uint8 = 1,
+    Int8 = 2,
+    Uint16 = 3,
+    Int16 = 4,
+    Uint32 = 5,
+    Int32 = 6,
+    Uint64 = 7,
+    Int64 = 8,
+    Real32 = 9,
+    Real64 = 10,
+    Custom = 13,
+}
+
+impl TryFrom<u8> for ParamExtType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            1 => ParamExtType::Uint8,
+            2 => ParamExtType::Int8,
+            3 => ParamExtType::Uint16,
+            4 => ParamExtType::Int16,
+            5 => ParamExtType::Uint32,
+            6 => ParamExtType::Int32,
+            7 => ParamExtType::Uint64,
+            8 => ParamExtType::Int64,
+            9 => ParamExtType::Real32,
+            10 => ParamExtType::Real64,
+            13 => ParamExtType::Custom,
+            _ => return Err(Error::UnsupportedType),
+        })
+    }
+}
+
+/// The result of a set request in the extended protocol, corresponds to `PARAM_ACK`.
+///
+/// Values are set immediately, so `PARAM_ACK_IN_PROGRESS` is never sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum ParamAck {
+    /// The parameter value was accepted and set
+    Accepted = 0,
+    /// The parameter value is of an unsupported type
+    ValueUnsupported = 1,
+    /// Setting the parameter failed
+    Failed = 2,
+}
+
+impl Value {
+    /// Get the extended protocol type of this [`Value`]
+    #[must_use]
+    pub fn ext_type(&self) -> ParamExtType {
+        match self {
+            Value::U8(_) => ParamExtType::Uint8,
+            Value::I8(_) => ParamExtType::Int8,
+            Value::U16(_) => ParamExtType::Uint16,
+            Value::I16(_) => ParamExtType::Int16,
+            Value::U32(_) => ParamExtType::Uint32,
+            Value::I32(_) => ParamExtType::Int32,
+            Value::U64(_) => ParamExtType::Uint64,
+            Value::I64(_) => ParamExtType::Int64,
+            Value::F32(_) => ParamExtType::Real32,
+            Value::F64(_) => ParamExtType::Real64,
+        }
+    }
+
+    /// Get the extended protocol payload of this [`Value`]
+    ///
+    /// The value is stored in little-endian byte order at the start
+    /// of the payload, with the remaining bytes set to zero.
+    #[must_use]
+    pub fn into_ext_bytes(&self) -> [u8; EXT_VALUE_LEN] {
+        let mut bytes = [0u8; EXT_VALUE_LEN];
+        let mut write = |le_bytes: &[u8]| bytes[..le_bytes.len()].copy_from_slice(le_bytes);
+
+        match self {
+            Value::U8(v) => write(&v.to_le_bytes()),
+            Value::I8(v) => write(&v.to_le_bytes()),
+            Value::U16(v) => write(&v.to_le_bytes()),
+            Value::I16(v) => write(&v.to_le_bytes()),
+            Value::U32(v) => write(&v.to_le_bytes()),
+            Value::I32(v) => write(&v.to_le_bytes()),
+            Value::U64(v) => write(&v.to_le_bytes()),
+            Value::I64(v) => write(&v.to_le_bytes()),
+            Value::F32(v) => write(&v.to_le_bytes()),
+            Value::F64(v) => write(&v.to_le_bytes()),
+        }
+
+        bytes
+    }
+
+    /// Decode an extended protocol payload of the given type into a [`Value`]
+    ///
+    /// Returns `None` for [`ParamExtType::Custom`], which cannot be represented.
+    #[must_use]
+    pub fn from_ext_bytes(bytes: &[u8; EXT_VALUE_LEN], param_type: ParamExtType) -> Option<Value> {
+        fn le<const N: usize>(bytes: &[u8; EXT_VALUE_LEN]) -> [u8; N] {
+            let mut le_bytes = [0u8; N];
+            le_bytes.copy_from_slice(&bytes[..N]);
+            le_bytes
+        }
+
+        Some(match param_type {
+            ParamExtType::Uint8 => Value::U8(u8::from_le_bytes(le(bytes))),
+            ParamExtType::Int8 => Value::I8(i8::from_le_bytes(le(bytes))),
+            ParamExtType::Uint16 => Value::U16(u16::from_le_bytes(le(bytes))),
+            ParamExtType::Int16 => Value::I16(i16::from_le_bytes(le(bytes))),
+            ParamExtType::Uint32 => Value::U32(u32::from_le_bytes(le(bytes))),
+            ParamExtType::Int32 => Value::I32(i32::from_le_bytes(le(bytes))),
+            ParamExtType::Uint64 => Value::U64(u64::from_le_bytes(le(bytes))),
+            ParamExtType::Int64 => Value::I64(i64::from_le_bytes(le(bytes))),
+            ParamExtType::Real32 => Value::F32(f32::from_le_bytes(le(bytes))),
+            ParamExtType::Real64 => Value::F64(f64::from_le_bytes(le(bytes))),
+            ParamExtType::Custom => return None,
+        })
+    }
+}
+
+// The 64-bit types cannot be represented in the 32-bit payload of the
+// basic protocol, so the bytewise conversions are lossy. The integers keep
+// their lower 32 bits, while the float is numerically converted. The numeric
+// conversions are checked, similar to the ones of the smaller types, and
+// `Value::encode` rejects values which would be truncated.
+
+impl Primitive for u64 {
+    fn from_bytewise(val: f32) -> u64 {
+        u64::from(val.to_bits())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn into_bytewise(self) -> f32 {
+        f32::from_bits(self as u32)
+    }
+
+    fn into_value(self) -> Value {
+        Value::U64(self)
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #[allow(clippy::cast_precision_loss, clippy::float_cmp)]
+    fn from_c_cast(val: f32) -> Result<u64, Error> {
+        // The upper bound is rounded to exactly 2^64
+        if !(0.0..u64::MAX as f32).contains(&val) {
+            return Err(Error::OutOfRange);
+        }
+
+        let int = val as u64;
+        if int as f32 == val {
+            Ok(int)
+        } else {
+            Err(Error::PrecisionLoss)
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn into_c_cast(self) -> Result<f32, Error> {
+        let val = self as f32;
+        if val as i128 == i128::from(self) {
+            Ok(val)
+        } else {
+            Err(Error::PrecisionLoss)
+        }
+    }
+}
+
+impl Primitive for i64 {
+    #[allow(clippy::cast_possible_wrap)]
+    fn from_bytewise(val: f32) -> i64 {
+        i64::from(val.to_bits() as i32)
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn into_bytewise(self) -> f32 {
+        f32::from_bits(self as u32)
+    }
+
+    fn into_value(self) -> Value {
+        Value::I64(self)
+    }
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::float_cmp
+    )]
+    fn from_c_cast(val: f32) -> Result<i64, Error> {
+        // The bounds are exactly -2^63 and 2^63, since the maximum is rounded up
+        const END: f32 = i64::MAX as f32;
+        if !(i64::MIN as f32..END).contains(&val) {
+            return Err(Error::OutOfRange);
+        }
+
+        let int = val as i64;
+        if int as f32 == val {
+            Ok(int)
+        } else {
+            Err(Error::PrecisionLoss)
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    fn into_c_cast(self) -> Result<f32, Error> {
+        let val = self as f32;
+        if val as i128 == i128::from(self) {
+            Ok(val)
+        } else {
+            Err(Error::PrecisionLoss)
+        }
+    }
+}
+
+impl Primitive for f64 {
+    fn from_bytewise(val: f32) -> f64 {
+        f64::from(val)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn into_bytewise(self) -> f32 {
+        self as f32
+    }
+
+    fn into_value(self) -> Value {
+        Value::F64(self)
+    }
+
+    fn from_c_cast(val: f32) -> Result<f64, Error> {
+        Ok(f64::from(val))
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::float_cmp)]
+    fn into_c_cast(self) -> Result<f32, Error> {
+        let val = self as f32;
+        if f64::from(val) == self || self.is_nan() {
+            Ok(val)
+        } else if val.is_infinite() {
+            Err(Error::OutOfRange)
+        } else {
+            Err(Error::PrecisionLoss)
+        }
+    }
+}
+
+/// A decoded request of the extended parameter protocol.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ExtRequest {
+    /// Corresponds to `PARAM_EXT_REQUEST_LIST`, asking for all parameters to be sent.
+    List,
+    /// Corresponds to `PARAM_EXT_REQUEST_READ`. If `param_index` is `-1` the
+    /// parameter is looked up using `param_id`, otherwise by its index.
+    Read { param_id: Ident, param_index: i16 },
+    /// Corresponds to `PARAM_EXT_SET`, with the raw payload and type code.
+    Set {
+        param_id: Ident,
+        param_value: [u8; EXT_VALUE_LEN],
+        param_type: u8,
+    },
+}
+
+/// An outgoing acknowledgement of a set request, corresponds to `PARAM_EXT_ACK`.
+///
+/// If the set request was not accepted, the parameter holds the current value.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ExtAck {
+    pub param: Parameter,
+    pub result: ParamAck,
+}
+
+/// An outgoing message of the extended parameter protocol.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ExtResponse {
+    /// Corresponds to `PARAM_EXT_VALUE`, use [`Value::into_ext_bytes`] for the payload.
+    Value(Response),
+    /// Corresponds to `PARAM_EXT_ACK`
+    Ack(ExtAck),
+}
+
+/// A transport-agnostic server for the extended Mavlink parameter protocol.
+///
+/// This works like the [`ParamServer`], except that set requests are answered
+/// with an [`ExtAck`] and values are transferred as 128-byte payloads.
+pub struct ExtParamServer<'a> {
+    server: ParamServer<'a>,
+}
+
+impl<'a> ExtParamServer<'a> {
+    /// Create a new server for the given tree
+    pub fn new(tree: &'a mut dyn Tree) -> Self {
+        Self {
+            server: ParamServer::new(tree),
+        }
+    }
+
+    /// Create a new server for the given tree with a "root" name defined
+    pub fn new_named(tree: &'a mut dyn Tree, name: &'a str) -> Self {
+        Self {
+            server: ParamServer::new_named(tree, name),
+        }
+    }
+
+    /// Call the hook with the new value of every parameter changed through this server,
+    /// see [`ParamServer::with_change_hook`].
+    #[must_use]
+    pub fn with_change_hook(self, hook: &'a mut dyn FnMut(&Response)) -> Self {
+        Self {
+            server: self.server.with_change_hook(hook),
+        }
+    }
+
+    /// The number of parameters served
+    #[must_use]
+    pub fn param_count(&self) -> u16 {
+        self.server.param_count()
+    }
+
+    /// Returns `true` while a list request is still being served
+    #[must_use]
+    pub fn is_listing(&self) -> bool {
+        self.server.is_listing()
+    }
+
+    /// Handle an incoming request.
+    ///
+    /// Read requests are answered with a value and set requests with an acknowledgement,
+    /// while a list request returns `Ok(None)` and starts producing responses through
+    /// [`ExtParamServer::poll`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoSuchParameter`] if the requested parameter does not exist.
+    pub fn handle(&mut self, request: &ExtRequest) -> Result<Option<ExtResponse>, Error> {
+        match request {
+            ExtRequest::List => self.server.handle(&Request::List).map(|_| None),
+            ExtRequest::Read {
+                param_id,
+                param_index,
+            } => {
+                let request = Request::Read {
+                    param_id: param_id.clone(),
+                    param_index: *param_index,
+                };
+                let response = self.server.handle(&request)?;
+                Ok(response.map(ExtResponse::Value))
+            }
+            ExtRequest::Set {
+                param_id,
+                param_value,
+                param_type,
+            } => {
+                let current = self
+                    .server
+                    .read_ident(param_id)
+                    .ok_or(Error::NoSuchParameter)?;
+
+                let value = ParamExtType::try_from(*param_type)
+                    .ok()
+                    .and_then(|param_type| Value::from_ext_bytes(param_value, param_type));
+
+                let ack = match value.map(|value| self.server.set(param_id, value)) {
+                    Some(Ok(response)) => ExtAck {
+                        param: response.param,
+                        result: ParamAck::Accepted,
+                    },
+                    None | Some(Err(Error::TypeMismatch { .. })) => ExtAck {
+                        param: current.param,
+                        result: ParamAck::ValueUnsupported,
+                    },
+                    Some(Err(Error::InvalidValue)) => ExtAck {
+                        param: current.param,
+                        result: ParamAck::Failed,
+                    },
+                    Some(Err(error)) => return Err(error),
+                };
+
+                Ok(Some(ExtResponse::Ack(ack)))
+            }
+        }
+    }
+
+    /// Retrieve the next response of an ongoing list request, if any.
+    pub fn poll(&mut self) -> Option<Response> {
+        self.server.poll()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        EXT_VALUE_LEN, ExtAck, ExtParamServer, ExtRequest, ExtResponse, ParamAck, ParamExtType,
+    };
+    use crate as mav_param;
+    use mav_param::{Encoding, Error, Ident, Parameter, Tree, Value};
+
+    #[derive(Tree, Default)]
+    struct Params {
+        count: u64,
+        offset: i64,
+        scale: f64,
+        small: u8,
+    }
+
+    #[test]
+    fn payload_round_trip() {
+        let values = [
+            Value::U8(200),
+            Value::I8(-100),
+            Value::U16(60_000),
+            Value::I16(-30_000),
+            Value::U32(4_000_000_000),
+            Value::I32(-2_000_000_000),
+            Value::U64(u64::MAX - 1),
+            Value::I64(i64::MIN + 1),
+            Value::F32(1.5),
+            Value::F64(-1e300),
+        ];
+
+        for value in values {
+            let bytes = value.into_ext_bytes();
+            assert_eq!(Value::from_ext_bytes(&bytes, value.ext_type()), Some(value));
+        }
+
+        let bytes = Value::U64(0x0102_0304_0506_0708).into_ext_bytes();
+        assert_eq!(bytes[..9], [8, 7, 6, 5, 4, 3, 2, 1, 0]);
+        assert_eq!(Value::from_ext_bytes(&bytes, ParamExtType::Custom), None);
+    }
+
+    #[test]
+    fn c_cast() {
+        use mav_param::value::{from_c_cast, into_c_cast};
+
+        assert_eq!(from_c_cast::<u64>(16_777_216.0), Ok(16_777_216));
+        assert_eq!(
+            from_c_cast::<u64>(18_446_744_073_709_551_616.0),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(
+            from_c_cast::<i64>(-9_223_372_036_854_775_808.0),
+            Ok(i64::MIN)
+        );
+        assert_eq!(
+            from_c_cast::<i64>(-(-9_223_372_036_854_775_808.0)),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(from_c_cast::<i64>(-0.5), Err(Error::PrecisionLoss));
+        assert_eq!(from_c_cast::<f64>(0.25), Ok(0.25));
+
+        assert_eq!(into_c_cast(1u64 << 40), Ok(1_099_511_627_776.0));
+        assert_eq!(into_c_cast(u64::MAX), Err(Error::PrecisionLoss));
+        assert_eq!(into_c_cast(-(1i64 << 24) - 1), Err(Error::PrecisionLoss));
+        assert_eq!(into_c_cast(0.1f64), Err(Error::PrecisionLoss));
+        assert_eq!(into_c_cast(1e300f64), Err(Error::OutOfRange));
+    }
+
+    #[test]
+    fn type_codes() {
+        for code in 0..=u8::MAX {
+            match ParamExtType::try_from(code) {
+                Ok(param_type) => assert_eq!(param_type as u8, code),
+                Err(error) => assert_eq!(error, Error::UnsupportedType),
+            }
+        }
+    }
+
+    #[test]
+    fn list_and_read() {
+        let mut params = Params {
+            count: u64::MAX,
+            ..Default::default()
+        };
+        let mut server = ExtParamServer::new_named(&mut params, "ext");
+        assert_eq!(server.param_count(), 4);
+
+        assert_eq!(server.handle(&ExtRequest::List), Ok(None));
+        let values: Vec<_> = core::iter::from_fn(|| server.poll())
+            .map(|response| response.param.value)
+            .collect();
+        assert_eq!(
+            values,
+            [
+                Value::U64(u64::MAX),
+                Value::I64(0),
+                Value::F64(0.0),
+                Value::U8(0)
+            ]
+        );
+
+        let request = ExtRequest::Read {
+            param_id: Ident::from_str_truncated("ext.count"),
+            param_index: -1,
+        };
+        let Ok(Some(ExtResponse::Value(response))) = server.handle(&request) else {
+            panic!("Expected a value response");
+        };
+        assert_eq!(response.param.value, Value::U64(u64::MAX));
+        assert_eq!(response.param_index, 0);
+
+        // The value does not fit in the basic protocol
+        assert_eq!(response.param_value(), Err(Error::OutOfRange));
+    }
+
+    #[test]
+    fn bytewise_range() {
+        assert_eq!(
+            Value::U64(u64::from(u32::MAX))
+                .encode(Encoding::Bytewise)
+                .map(f32::to_bits),
+            Ok(u32::MAX)
+        );
+        assert_eq!(
+            Value::U64(1 << 32).encode(Encoding::Bytewise),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(
+            Value::I64(-1).encode(Encoding::Bytewise).map(f32::to_bits),
+            Ok(u32::MAX)
+        );
+        assert_eq!(
+            Value::I64(i64::from(i32::MIN) - 1).encode(Encoding::Bytewise),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(
+            Value::F64(0.25)
+                .encode(Encoding::Bytewise)
+                .map(f32::to_bits),
+            Ok(0.25f32.to_bits())
+        );
+        assert_eq!(
+            Value::F64(1e300).encode(Encoding::Bytewise),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(
+            Value::F64(0.1).encode(Encoding::Bytewise),
+            Err(Error::PrecisionLoss)
+        );
+    }
+
+    #[test]
+    fn set() {
+        let mut params = Params::default();
+        let mut server = ExtParamServer::new_named(&mut params, "ext");
+
+        let ack = |ident: &str, value: Value, result: ParamAck| {
+            Ok(Some(ExtResponse::Ack(ExtAck {
+                param: Parameter {
+                    ident: Ident::from_str_truncated(ident),
+                    value,
+                },
+                result,
+            })))
+        };
+
+        let request = ExtRequest::Set {
+            param_id: Ident::from_str_truncated("ext.offset"),
+            param_value: Value::I64(-1 << 40).into_ext_bytes(),
+            param_type: ParamExtType::Int64 as u8,
+        };
+        assert_eq!(
+            server.handle(&request),
+            ack("ext.offset", Value::I64(-1 << 40), ParamAck::Accepted)
+        );
+
+        // Mismatching type
+        let request = ExtRequest::Set {
+            param_id: Ident::from_str_truncated("ext.scale"),
+            param_value: Value::F32(1.0).into_ext_bytes(),
+            param_type: ParamExtType::Real32 as u8,
+        };
+        assert_eq!(
+            server.handle(&request),
+            ack("ext.scale", Value::F64(0.0), ParamAck::ValueUnsupported)
+        );
+
+        // Custom and unknown types
+        for param_type in [ParamExtType::Custom as u8, 42] {
+            let request = ExtRequest::Set {
+                param_id: Ident::from_str_truncated("ext.small"),
+                param_value: [1; EXT_VALUE_LEN],
+                param_type,
+            };
+            assert_eq!(
+                server.handle(&request),
+                ack("ext.small", Value::U8(0), ParamAck::ValueUnsupported)
+            );
+        }
+
+        let request = ExtRequest::Set {
+            param_id: Ident::from_str_truncated("ext.nothing"),
+            param_value: [0; EXT_VALUE_LEN],
+            param_type: ParamExtType::Uint8 as u8,
+        };
+        assert_eq!(server.handle(&request), Err(Error::NoSuchParameter));
+
+        assert_eq!(params.offset, -1 << 40);
+    }
+}
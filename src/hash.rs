@@ -0,0 +1,156 @@
+use crate::{Encoding, Ident, Tree, iter};
+
+/// The identifier of the synthetic parameter holding the tree hash, as used by PX4
+pub const HASH_CHECK_IDENT: &str = "_HASH_CHECK";
+
+/// Compute a CRC32 over all parameters of the tree, like the `_HASH_CHECK` parameter of PX4.
+///
+/// The parameters are included in the order of their identifiers, each with the identifier
+/// followed by the 4 bytes of its bytewise encoded value, which is how PX4 and `QGroundControl`
+/// compute it. The hash only changes if a parameter is added, removed, renamed or changed,
+/// so a ground station can use it to validate its cached parameter list.
+///
+/// Note: Entries causing [`crate::Error::PathTooLong`] or [`crate::Error::DepthTooBig`] are
+/// skipped, as are values which can't be encoded bytewise, since they can't be sent either.
+#[must_use]
+pub fn tree_hash(tree: &dyn Tree, name: Option<&str>) -> u32 {
+    let mut crc = Crc32::from_state(0);
+    let mut previous: Option<Ident> = None;
+
+    // The parameters are not sorted, so without allocating, each step searches the next one
+    while let Some(param) = iter::ParamIter::new(tree, name)
+        .flatten()
+        .filter(|param| {
+            previous
+                .as_ref()
+                .is_none_or(|previous| param.ident.as_str() > previous.as_str())
+        })
+        .min_by(|a, b| a.ident.as_str().cmp(b.ident.as_str()))
+    {
+        if let Ok(value) = param.value.encode(Encoding::Bytewise) {
+            crc.update(param.ident.as_str().as_bytes());
+            crc.update(&value.to_le_bytes());
+        }
+        previous = Some(param.ident);
+    }
+
+    crc.state()
+}
+
+// CRC-32 (ISO-HDLC), as used by e.g. zlib and PX4
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+    #[cfg(feature = "flash")]
+    pub(crate) fn new() -> Self {
+        Crc32(0xFFFF_FFFF)
+    }
+
+    // Continue from a state without the initial and final inversion, like `crc32part` of PX4
+    pub(crate) fn from_state(state: u32) -> Self {
+        Crc32(state)
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u32::from(*byte);
+            for _ in 0..8 {
+                let mask = (self.0 & 1).wrapping_neg();
+                self.0 = (self.0 >> 1) ^ (0xEDB8_8320 & mask);
+            }
+        }
+    }
+
+    pub(crate) fn state(&self) -> u32 {
+        self.0
+    }
+
+    #[cfg(feature = "flash")]
+    pub(crate) fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Crc32, tree_hash};
+    use crate as mav_param;
+    use mav_param::{Tree, Value, get_value_mut};
+
+    #[derive(Tree, Default)]
+    struct Params {
+        a: u8,
+        b: f32,
+    }
+
+    #[derive(Tree, Default)]
+    struct Renamed {
+        a: u8,
+        #[tree(rename = "c")]
+        b: f32,
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        let mut crc = Crc32::from_state(0xFFFF_FFFF);
+        crc.update(b"123456789");
+        assert_eq!(!crc.state(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn px4_hash() {
+        #[derive(Tree)]
+        struct Px4 {
+            #[tree(rename = "SYS_AUTOSTART")]
+            autostart: i32,
+            #[tree(rename = "MC_ROLL_P")]
+            roll_p: f32,
+            #[tree(rename = "BAT1_N_CELLS")]
+            cells: i32,
+        }
+
+        // Computed like `param_hash_check` of PX4, over the parameters sorted by name
+        let params = Px4 {
+            autostart: 4001,
+            roll_p: 6.5,
+            cells: 4,
+        };
+        assert_eq!(tree_hash(&params, None), 0x6EED_82C0);
+    }
+
+    #[test]
+    fn hash_changes() {
+        let mut params = Params::default();
+        let hash = tree_hash(&params, Some("p"));
+
+        // Stable for the same tree
+        assert_eq!(tree_hash(&params, Some("p")), hash);
+
+        // Depends on the names and values
+        assert_ne!(tree_hash(&params, None), hash);
+        assert_ne!(tree_hash(&Renamed::default(), Some("p")), hash);
+
+        assert!(
+            get_value_mut(&mut params, "a")
+                .unwrap()
+                .try_assign(Value::U8(1))
+        );
+        assert_ne!(tree_hash(&params, Some("p")), hash);
+    }
+
+    #[cfg(feature = "ext")]
+    #[test]
+    fn hash_wide_values() {
+        #[derive(Tree, Default)]
+        struct Wide {
+            count: u64,
+        }
+
+        // Once the value no longer fits the bytewise encoding it can't be sent, so it is left out
+        let mut wide = Wide { count: 1 };
+        let hash = tree_hash(&wide, None);
+        wide.count |= 1 << 32;
+        assert_ne!(tree_hash(&wide, None), hash);
+        assert_eq!(tree_hash(&wide, None), 0);
+    }
+}
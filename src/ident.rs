@@ -1,9 +1,9 @@
-/// The length of a MavLink parameters identifier/path
+/// The length of a `MavLink` parameters identifier/path
 const MAX_NAMED_LEN: usize = 16;
 
 /// Describes the identifier/path of a parameter
 ///
-/// This is designed to be fully compatible with the MavLink
+/// This is designed to be fully compatible with the `MavLink`
 /// parameter protocol, by being a 16-byte null-terminated String
 ///
 /// To get a utf8 string slice (`&str`), use [`Ident::as_str`]
@@ -98,18 +98,18 @@ impl Ident {
     }
 
     /// Expose the inner string slice
+    #[must_use]
     pub fn as_str(&self) -> &str {
-        // It is fine to unwrap since we always
-        // only push valid utf8 to the buffer
-        let result = core::str::from_utf8(&self.buf[..self.len]);
-        debug_assert!(result.is_ok());
+        // We only push valid utf8 to the buffer, but a deserialized identifier
+        // is not validated, so it is checked here rather than trusted
+        let result = self.buf.get(..self.len).map(core::str::from_utf8);
+        debug_assert!(matches!(result, Some(Ok(_))));
 
-        // SAFETY: In all places we add data to the buffer,
-        // it is either &str or validated utf8.
-        unsafe { result.unwrap_unchecked() }
+        result.and_then(Result::ok).unwrap_or_default()
     }
 
-    /// Expose the inner null-terminated string. Compatible with MavLink parameter names
+    /// Expose the inner null-terminated string. Compatible with `MavLink` parameter names
+    #[must_use]
     pub fn as_raw(&self) -> &[u8; MAX_NAMED_LEN] {
         &self.buf
     }
@@ -234,17 +234,17 @@ mod tests {
         let mut ident = super::Ident::new();
 
         // 15 characters
-        assert_eq!(ident.push_entry("xxxxxxxxxxxxxxx"), true);
+        assert!(ident.push_entry("xxxxxxxxxxxxxxx"));
         assert_eq!(ident.as_str().len(), 15);
         ident.pop_entry();
 
         // 16 characters
-        assert_eq!(ident.push_entry("xxxxxxxxxxxxxxxx"), true);
+        assert!(ident.push_entry("xxxxxxxxxxxxxxxx"));
         assert_eq!(ident.as_str().len(), 16);
         ident.pop_entry();
 
         // 17 characters (fails)
-        assert_eq!(ident.push_entry("xxxxxxxxxxxxxxxxx"), false);
+        assert!(!ident.push_entry("xxxxxxxxxxxxxxxxx"));
         assert_eq!(ident.as_str().len(), 0);
     }
 }
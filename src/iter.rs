@@ -1,28 +1,18 @@
 use heapless::Vec;
 
-use crate::{Error, NodeRef, Parameter, Tree};
+use crate::{Error, Ident, NodeRef, Parameter, Tree};
 
 /// Maximum ident/path depth
 pub const MAX_IDENT_DEPTH: usize = 5;
 
-/// The state of a single "level" of tree iteration
-struct Segment<'a> {
-    // Reference to the tree at this level
-    tree: &'a dyn Tree,
-    // Current index in the tree's entries
-    index: usize,
-}
-
 /// An iterator that traverses a parameter tree and yields each leaf parameter.
 ///
 /// This iterator performs a depth-first traversal of the parameter tree, building
 /// parameter identifiers using dot notation (e.g., "sys.sub.param").
 /// It handles errors related to path length and tree depth limitations.
 pub struct ParamIter<'a> {
-    // Single path buffer that's modified during traversal
-    ident_buffer: crate::ident::Ident,
-    // Stack stores only minimal data for traversal state
-    stack: Vec<Segment<'a>, MAX_IDENT_DEPTH>,
+    tree: &'a dyn Tree,
+    cursor: ParamCursor,
 }
 
 impl<'a> ParamIter<'a> {
@@ -30,7 +20,41 @@ impl<'a> ParamIter<'a> {
     ///
     /// Note: A tree is never aware of its parents, so they are not included in the path.
     pub fn new(tree: &'a dyn Tree, name: Option<&str>) -> Self {
-        let mut ident_buffer = crate::ident::Ident::new();
+        Self {
+            tree,
+            cursor: ParamCursor::new(name),
+        }
+    }
+}
+
+impl Iterator for ParamIter<'_> {
+    type Item = Result<Parameter, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.cursor.next(self.tree)
+    }
+}
+
+/// The position of a depth-first traversal of a parameter tree, without borrowing the tree.
+///
+/// The cursor only stores an index into the [`Tree::entries`] of each level, and resolves
+/// the current level from the root on every call to [`ParamCursor::next`]. The tree may
+/// therefore be modified between calls, while yielding the same sequence as [`ParamIter`].
+///
+/// Note: The tree passed to [`ParamCursor::next`] must always be the same "root" tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamCursor {
+    // Single path buffer that's modified during traversal
+    ident_buffer: Ident,
+    // The index of the next entry to process at each level
+    stack: Vec<usize, MAX_IDENT_DEPTH>,
+}
+
+impl ParamCursor {
+    /// Creates a new cursor at the start of a tree, with an optional "root" name.
+    #[must_use]
+    pub fn new(name: Option<&str>) -> Self {
+        let mut ident_buffer = Ident::new();
 
         if let Some(name) = name {
             _ = ident_buffer.push_entry(name);
@@ -38,25 +62,26 @@ impl<'a> ParamIter<'a> {
 
         // Push the tree root to begin traversal
         let mut stack = Vec::new();
-        let _ = stack.push(Segment { tree, index: 0 });
+        let _ = stack.push(0);
 
         Self {
             ident_buffer,
             stack,
         }
     }
-}
-
-impl Iterator for ParamIter<'_> {
-    type Item = Result<Parameter, Error>;
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Advance the cursor, yielding the next parameter of the tree.
+    ///
+    /// Returns `None` once the traversal is done, or if the current position
+    /// can no longer be resolved in the tree.
+    pub fn next(&mut self, root: &dyn Tree) -> Option<Result<Parameter, Error>> {
         loop {
-            let segment = self.stack.last_mut()?;
-            let entries = segment.tree.entries();
+            let tree = self.resolve(root)?;
+            let index = self.stack.last_mut()?;
+            let entries = tree.entries();
 
             // Check if we've processed all entries in the current tree
-            if segment.index >= entries.len() {
+            if *index >= entries.len() {
                 // We're done with this node
                 self.stack.pop()?;
 
@@ -69,8 +94,9 @@ impl Iterator for ParamIter<'_> {
             }
 
             // Get the next entry to process
-            let entry_name = entries[segment.index];
-            segment.index += 1;
+            let entry_index = *index;
+            let entry_name = entries[entry_index];
+            *index += 1;
 
             // Add this segment to the path (temporarily)
             if !self.ident_buffer.push_entry(entry_name) {
@@ -80,7 +106,7 @@ impl Iterator for ParamIter<'_> {
                 )));
             }
 
-            match segment.tree.get_ref(entry_name)? {
+            match tree.get_ref_at(entry_index)? {
                 NodeRef::Value(value) => {
                     // Create a copy of the current path for the return value
                     let ident = self.ident_buffer.clone();
@@ -90,25 +116,69 @@ impl Iterator for ParamIter<'_> {
 
                     return Some(Ok(Parameter { ident, value }));
                 }
-                NodeRef::Tree(tree) => {
+                NodeRef::Tree(_) => {
                     // Push this node for traversal
-                    if self.stack.push(Segment { tree, index: 0 }).is_err() {
-                        return Some(Err(Error::DepthTooBig(
-                            self.ident_buffer.clone(),
-                            entry_name,
-                        )));
+                    if self.stack.push(0).is_err() {
+                        let ident = self.ident_buffer.clone();
+
+                        // The subtree is skipped, so remove its segment again
+                        self.ident_buffer.pop_entry();
+
+                        return Some(Err(Error::DepthTooBig(ident, entry_name)));
                     }
                 }
             }
         }
     }
+
+    /// Resolve the tree of the deepest level from the root
+    fn resolve<'t>(&self, mut tree: &'t dyn Tree) -> Option<&'t dyn Tree> {
+        let (_, parents) = self.stack.split_last()?;
+
+        // The index of a parent level has already moved past the entry being traversed
+        for index in parents {
+            match tree.get_ref_at(index.checked_sub(1)?)? {
+                NodeRef::Tree(subtree) => tree = subtree,
+                NodeRef::Value(_) => return None,
+            }
+        }
+
+        Some(tree)
+    }
+}
+
+/// Count the parameters yielded by the iterator, skipping errors.
+pub(crate) fn count(iter: ParamIter<'_>) -> u16 {
+    u16::try_from(iter.flatten().count()).unwrap_or(u16::MAX)
+}
+
+/// Get the parameter at the given index, skipping errors.
+pub(crate) fn nth(iter: ParamIter<'_>, index: u16) -> Option<Parameter> {
+    iter.flatten().nth(index.into())
+}
+
+/// Advance the cursor to the next parameter, skipping errors.
+pub(crate) fn next_param(cursor: &mut ParamCursor, root: &dyn Tree) -> Option<Parameter> {
+    core::iter::from_fn(|| cursor.next(root)).find_map(Result::ok)
+}
+
+/// Get the index of the parameter with the given identifier, skipping errors.
+pub(crate) fn position(iter: ParamIter<'_>, ident: &str) -> Option<u16> {
+    let index = iter
+        .flatten()
+        .position(|param| param.ident.as_str() == ident)?;
+    u16::try_from(index).ok()
 }
 
 #[cfg(test)]
 mod tests {
+    use super::ParamCursor;
     use crate as mav_param;
     use mav_param::Error;
-    use mav_param::{Tree, Value, param_iter_named};
+    use mav_param::{
+        NodeMut, NodeRef, Tree, Value, get_by_index, get_by_index_named, index_of, index_of_named,
+        param_count, param_count_named, param_iter, param_iter_named,
+    };
 
     #[test]
     fn basic_iteration() {
@@ -137,12 +207,12 @@ mod tests {
         let params = TestParams {
             subtree: SubTree {
                 leaf1: 42,
-                leaf2: 3.14,
+                leaf2: 3.25,
                 deeper: DeepSubTree { val: -5 },
             },
             value1: 10,
             value2: -100,
-            float_val: 2.718,
+            float_val: 2.5,
         };
 
         // Collect all parameters into a vector
@@ -154,12 +224,12 @@ mod tests {
         assert_eq!(results.len(), 5, "Should iterate over 5 leaf values");
 
         // Check specific parameters
-        let expected_params = vec![
+        let expected_params = [
             ("test.sub.leaf1", Value::U32(42)),
-            ("test.sub.leaf2", Value::F32(3.14)),
+            ("test.sub.leaf2", Value::F32(3.25)),
             ("test.value1", Value::U8(10)),
             ("test.value2", Value::I16(-100)),
-            ("test.float_val", Value::F32(2.718)),
+            ("test.float_val", Value::F32(2.5)),
         ];
 
         for (param, (expected_path, expected_value)) in results.iter().zip(expected_params.iter()) {
@@ -224,11 +294,10 @@ mod tests {
         };
 
         // Try to iterate - should encounter DepthTooBig error
-        let mut iter = param_iter_named(&deep_tree, "d");
         let mut found_depth_error = false;
 
         // We should be able to traverse until we hit the max depth
-        while let Some(result) = iter.next() {
+        for result in param_iter_named(&deep_tree, "d") {
             if let Err(Error::DepthTooBig(_, _)) = result {
                 found_depth_error = true;
                 break;
@@ -237,4 +306,355 @@ mod tests {
 
         assert!(found_depth_error, "Should encounter a DepthTooBig error");
     }
+
+    #[test]
+    fn continue_after_depth_error() {
+        #[derive(Tree)]
+        struct TestParams {
+            d1: Depth1,
+            b: u8,
+        }
+
+        #[derive(Tree)]
+        struct Depth1 {
+            d2: ((((u8,),),),),
+            c: u8,
+        }
+
+        let params = TestParams {
+            d1: Depth1 {
+                d2: ((((4,),),),),
+                c: 5,
+            },
+            b: 6,
+        };
+
+        // The skipped subtree is removed from the path, so the following idents are intact
+        let results: Vec<_> = param_iter_named(&params, "t").collect();
+        assert!(matches!(results[0], Err(Error::DepthTooBig(_, "0"))));
+        let idents: Vec<_> = results.iter().flatten().map(|p| p.ident.as_str()).collect();
+        assert_eq!(idents, ["t.d1.c", "t.b"]);
+    }
+
+    #[test]
+    fn index_lookup() {
+        #[derive(Tree)]
+        struct TestParams {
+            a: u8,
+            sub: (u16, f32),
+            b: i32,
+        }
+
+        let params = TestParams {
+            a: 1,
+            sub: (2, 3.0),
+            b: 4,
+        };
+
+        assert_eq!(param_count(&params), 4);
+        assert_eq!(param_count_named(&params, "test"), 4);
+
+        for (index, param) in (0..).zip(param_iter(&params).flatten()) {
+            assert_eq!(get_by_index(&params, index), Some(param.clone()));
+            assert_eq!(index_of(&params, param.ident.as_str()), Some(index));
+        }
+
+        for (index, param) in (0..).zip(param_iter_named(&params, "test").flatten()) {
+            assert_eq!(
+                get_by_index_named(&params, "test", index),
+                Some(param.clone())
+            );
+            assert_eq!(
+                index_of_named(&params, "test", param.ident.as_str()),
+                Some(index)
+            );
+        }
+
+        assert_eq!(index_of(&params, ".sub.1"), Some(2));
+        assert_eq!(index_of_named(&params, "test", ".test.sub.1"), Some(2));
+        assert_eq!(index_of(&params, "sub.y"), None);
+        assert_eq!(index_of_named(&params, "test", "sub.1"), None);
+        assert_eq!(get_by_index(&params, 4), None);
+    }
+
+    #[test]
+    fn index_skips_errors() {
+        #[derive(Tree)]
+        struct TestParams {
+            a: u8,
+            very_long_name_x: (u8, u8),
+            d1: Depth1,
+            b: u8,
+        }
+
+        #[derive(Tree)]
+        struct Depth1 {
+            d2: ((((u8,),),),),
+            c: u8,
+        }
+
+        let params = TestParams {
+            a: 1,
+            very_long_name_x: (2, 3),
+            d1: Depth1 {
+                d2: ((((4,),),),),
+                c: 5,
+            },
+            b: 6,
+        };
+
+        let results: Vec<_> = param_iter_named(&params, "t").collect();
+        assert!(matches!(
+            results[1],
+            Err(Error::PathTooLong(_, "very_long_name_x"))
+        ));
+        assert!(matches!(results[2], Err(Error::DepthTooBig(_, "0"))));
+
+        // The iteration continues correctly after the errors
+        let idents: Vec<_> = results.iter().flatten().map(|p| p.ident.as_str()).collect();
+        assert_eq!(idents, ["t.a", "t.d1.c", "t.b"]);
+
+        assert_eq!(param_count_named(&params, "t"), 3);
+        assert_eq!(index_of_named(&params, "t", "t.d1.c"), Some(1));
+        assert_eq!(
+            get_by_index_named(&params, "t", 2).map(|p| p.value),
+            Some(Value::U8(6))
+        );
+    }
+
+    #[test]
+    fn index_accessors() {
+        #[derive(Tree)]
+        struct TestParams {
+            a: u8,
+            #[tree(rename = "tup")]
+            tuple: (u16, i8),
+            arr: [f32; 2],
+        }
+
+        fn assert_agree(tree: &mut dyn Tree) {
+            for (index, entry) in tree.entries().iter().enumerate() {
+                match (tree.get_ref_at(index), tree.get_ref(entry)) {
+                    (Some(NodeRef::Value(a)), Some(NodeRef::Value(b))) => assert_eq!(a, b),
+                    (Some(NodeRef::Tree(a)), Some(NodeRef::Tree(b))) => {
+                        assert_eq!(a.entries(), b.entries());
+                    }
+                    _ => panic!("Index and name accessors disagree for {entry}"),
+                }
+
+                let value = match tree.get_mut_at(index) {
+                    Some(NodeMut::Value(value_mut)) => Some(value_mut.owned()),
+                    Some(NodeMut::Tree(subtree)) => {
+                        assert_agree(subtree);
+                        None
+                    }
+                    None => panic!("Missing mutable entry for {entry}"),
+                };
+
+                if let Some(NodeMut::Value(value_mut)) = tree.get_mut(entry) {
+                    assert_eq!(value, Some(value_mut.owned()));
+                }
+            }
+
+            let len = tree.entries().len();
+            assert!(tree.get_ref_at(len).is_none());
+            assert!(tree.get_mut_at(len).is_none());
+        }
+
+        let mut params = TestParams {
+            a: 1,
+            tuple: (2, -3),
+            arr: [4.0, 5.0],
+        };
+
+        assert_agree(&mut params);
+    }
+
+    #[test]
+    fn cursor() {
+        #[derive(Tree)]
+        struct TestParams {
+            a: u8,
+            very_long_name_x: (u8, u8),
+            d1: Depth1,
+            b: u8,
+        }
+
+        #[derive(Tree)]
+        struct Depth1 {
+            d2: ((((u8,),),),),
+            c: [u16; 2],
+        }
+
+        let mut params = TestParams {
+            a: 1,
+            very_long_name_x: (2, 3),
+            d1: Depth1 {
+                d2: ((((4,),),),),
+                c: [5, 6],
+            },
+            b: 7,
+        };
+
+        // The cursor yields the same sequence and errors as the iterator
+        let mut cursor = ParamCursor::new(Some("t"));
+        let results: Vec<_> = core::iter::from_fn(|| cursor.next(&params)).collect();
+        assert_eq!(results, param_iter_named(&params, "t").collect::<Vec<_>>());
+        assert_eq!(results.len(), 6);
+        assert_eq!(cursor.next(&params), None);
+
+        // The tree can be modified while the cursor is in progress
+        let mut cursor = ParamCursor::new(Some("t"));
+        cursor.next(&params).unwrap().unwrap();
+        cursor.next(&params).unwrap().unwrap_err();
+        cursor.next(&params).unwrap().unwrap_err();
+
+        params.d1.c[0] = 50;
+        let param = cursor.next(&params).unwrap().unwrap();
+        assert_eq!(param.ident.as_str(), "t.d1.c.0");
+        assert_eq!(param.value, Value::U16(50));
+
+        params.b = 70;
+        let rest: Vec<_> = core::iter::from_fn(|| cursor.next(&params))
+            .flatten()
+            .map(|p| p.value)
+            .collect();
+        assert_eq!(rest, [Value::U16(6), Value::U8(70)]);
+    }
+
+    #[test]
+    fn tuple_struct() {
+        #[derive(Tree)]
+        struct Gains(f32, f32, #[tree(rename = "d")] f32);
+
+        #[derive(Tree)]
+        struct TestParams {
+            roll: Gains,
+            pair: Pair,
+        }
+
+        #[derive(Tree)]
+        struct Pair(u8, i16);
+
+        let mut params = TestParams {
+            roll: Gains(1.0, 0.5, 0.25),
+            pair: Pair(1, -2),
+        };
+
+        let idents: Vec<_> = param_iter(&params)
+            .flatten()
+            .map(|p| (p.ident.as_str().to_owned(), p.value))
+            .collect();
+        assert_eq!(
+            idents,
+            [
+                ("roll.0".to_owned(), Value::F32(1.0)),
+                ("roll.1".to_owned(), Value::F32(0.5)),
+                ("roll.d".to_owned(), Value::F32(0.25)),
+                ("pair.0".to_owned(), Value::U8(1)),
+                ("pair.1".to_owned(), Value::I16(-2)),
+            ]
+        );
+
+        assert!(
+            mav_param::get_value_mut(&mut params, "roll.d")
+                .unwrap()
+                .try_assign(Value::F32(0.75))
+        );
+        assert_eq!(
+            mav_param::get_value(&params, "roll.d"),
+            Some(Value::F32(0.75))
+        );
+        assert_eq!(index_of(&params, "pair.1"), Some(4));
+    }
+
+    #[test]
+    fn tagged_enum() {
+        #[derive(Tree, Default)]
+        struct Ekf {
+            q: f32,
+            r: f32,
+        }
+
+        #[derive(Tree)]
+        enum Estimator {
+            Ekf(Ekf),
+            Complementary { gain: f32 },
+            Disabled,
+        }
+
+        #[derive(Tree, Debug, PartialEq)]
+        #[tree(tag = "mode")]
+        enum Output {
+            Pwm { rate: u16 },
+            Dshot(u16, #[tree(rename = "tlm")] bool),
+        }
+
+        #[derive(Tree)]
+        struct TestParams {
+            est: Estimator,
+            out: Output,
+        }
+
+        let listed = |params: &TestParams| -> Vec<_> {
+            param_iter(params)
+                .flatten()
+                .map(|p| (p.ident.as_str().to_owned(), p.value))
+                .collect()
+        };
+
+        let mut params = TestParams {
+            est: Estimator::Complementary { gain: 0.5 },
+            out: Output::Pwm { rate: 400 },
+        };
+
+        // Only the fields of the active variants are listed
+        assert_eq!(
+            listed(&params),
+            [
+                ("est.tag".to_owned(), Value::U8(1)),
+                ("est.gain".to_owned(), Value::F32(0.5)),
+                ("out.mode".to_owned(), Value::U8(0)),
+                ("out.rate".to_owned(), Value::U16(400)),
+            ]
+        );
+
+        // Inactive variants cannot be written
+        assert!(mav_param::get_value_mut(&mut params, "out.0").is_none());
+
+        // Changing the tag selects the default of the variant
+        let mut tag = mav_param::get_value_mut(&mut params, "est.tag").unwrap();
+        assert_eq!(tag.assign(Value::U8(3)), Err(Error::InvalidValue));
+        assert_eq!(tag.assign(Value::U8(0)), Ok(()));
+        assert!(
+            mav_param::get_value_mut(&mut params, "est.ekf.r")
+                .unwrap()
+                .try_assign(Value::F32(2.0))
+        );
+        assert!(
+            mav_param::get_value_mut(&mut params, "out.mode")
+                .unwrap()
+                .try_assign(Value::U8(1))
+        );
+        assert_eq!(params.out, Output::Dshot(0, false));
+
+        // Writing the active variant keeps its fields
+        assert!(
+            mav_param::get_value_mut(&mut params, "est.tag")
+                .unwrap()
+                .try_assign(Value::U8(0))
+        );
+        assert_eq!(
+            listed(&params)[..3],
+            [
+                ("est.tag".to_owned(), Value::U8(0)),
+                ("est.ekf.q".to_owned(), Value::F32(0.0)),
+                ("est.ekf.r".to_owned(), Value::F32(2.0)),
+            ]
+        );
+
+        params.est = Estimator::Disabled;
+        assert_eq!(param_count(&params), 4);
+        assert_eq!(index_of(&params, "out.tlm"), Some(3));
+    }
 }
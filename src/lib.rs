@@ -1,13 +1,31 @@
 #![cfg_attr(not(test), no_std)]
 #![warn(clippy::pedantic)]
 
+#[cfg(feature = "ext")]
+pub mod ext;
+pub mod hash;
 pub mod ident;
 pub mod iter;
+#[cfg(feature = "mavlink")]
+mod mavlink_impls;
+pub mod mirror;
+pub mod router;
+pub mod server;
+pub mod storage;
+pub mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod transaction;
 pub mod tree_impls;
 pub mod value;
+#[cfg(feature = "wire")]
+pub mod wire;
 
+pub use hash::tree_hash;
 pub use ident::Ident;
-pub use value::{Value, ValueMut};
+pub use tree_impls::option::Optional;
+pub use tree_impls::variants::{AllVariants, Variants};
+pub use value::{Encoding, ParamType, Validated, Value, ValueMut};
 
 pub use mav_param_derive::{Node, Tree};
 
@@ -23,6 +41,31 @@ pub enum Error {
     SequenceTooLong,
     /// The sequence is not valid utf8
     SequenceNotUtf8,
+    /// No parameter exists with the given identifier or index
+    NoSuchParameter,
+    /// The type of the value does not match the type of the parameter
+    TypeMismatch {
+        expected: value::ParamType,
+        received: value::ParamType,
+    },
+    /// The parameter type code is not known or not supported
+    UnsupportedType,
+    /// The parameter cannot be modified
+    ReadOnly,
+    /// The value is outside the range of the parameter type
+    OutOfRange,
+    /// The value cannot be represented exactly by the parameter type
+    PrecisionLoss,
+    /// The value is not accepted by the parameter, e.g. it is not a variant of an enum
+    InvalidValue,
+    /// There is not enough room to store the data
+    CapacityExceeded,
+    /// The bytes do not hold a complete and valid Mavlink frame
+    InvalidFrame,
+    /// The checksum of a Mavlink frame does not match its contents
+    BadChecksum,
+    /// The Mavlink message is not part of the parameter protocol
+    UnsupportedMessage,
 }
 
 /// A parameter combines a 16-byte identifier with a value.
@@ -41,6 +84,23 @@ pub trait Tree: Send + Sync {
     /// Retrieve a mutable reference to the node at a given path.
     fn get_mut<'a>(&'a mut self, node: &str) -> Option<NodeMut<'a>>;
 
+    /// Retrieve a reference to the node at a given index of [`Tree::entries`].
+    ///
+    /// The default implementation looks up the entry name, so implementors
+    /// should override this to avoid the string matching.
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get_ref(self.entries().get(index)?)
+    }
+
+    /// Retrieve a mutable reference to the node at a given index of [`Tree::entries`].
+    ///
+    /// The default implementation looks up the entry name, so implementors
+    /// should override this to avoid the string matching.
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        let entry = self.entries().get(index)?;
+        self.get_mut(entry)
+    }
+
     /// List all the entries (child names) at this level of the tree.
     fn entries(&self) -> &'static [&'static str];
 }
@@ -61,6 +121,50 @@ pub fn param_iter(tree: &dyn Tree) -> iter::ParamIter<'_> {
     iter::ParamIter::new(tree, None)
 }
 
+/// Returns the number of parameters in this tree
+///
+/// Note: Only parameters which are successfully yielded by [`param_iter`] are counted,
+/// so entries causing [`Error::PathTooLong`] or [`Error::DepthTooBig`] are not included.
+pub fn param_count(tree: &dyn Tree) -> u16 {
+    iter::count(param_iter(tree))
+}
+
+/// Returns the number of parameters in this tree with a "root" name defined
+///
+/// Note: Only parameters which are successfully yielded by [`param_iter_named`] are counted,
+/// so entries causing [`Error::PathTooLong`] or [`Error::DepthTooBig`] are not included.
+pub fn param_count_named(tree: &dyn Tree, name: &str) -> u16 {
+    iter::count(param_iter_named(tree, name))
+}
+
+/// Returns the parameter at the given index
+///
+/// Note: The index matches the position in [`param_iter`] when skipping errors.
+pub fn get_by_index(tree: &dyn Tree, index: u16) -> Option<Parameter> {
+    iter::nth(param_iter(tree), index)
+}
+
+/// Returns the parameter at the given index with a "root" name defined
+///
+/// Note: The index matches the position in [`param_iter_named`] when skipping errors.
+pub fn get_by_index_named(tree: &dyn Tree, name: &str, index: u16) -> Option<Parameter> {
+    iter::nth(param_iter_named(tree, name), index)
+}
+
+/// Returns the index of the parameter with the given identifier
+///
+/// Note: The index matches the position in [`param_iter`] when skipping errors.
+pub fn index_of(tree: &dyn Tree, ident: &str) -> Option<u16> {
+    iter::position(param_iter(tree), ident.trim_start_matches('.'))
+}
+
+/// Returns the index of the parameter with the given identifier, which includes the "root" name
+///
+/// Note: The index matches the position in [`param_iter_named`] when skipping errors.
+pub fn index_of_named(tree: &dyn Tree, name: &str, ident: &str) -> Option<u16> {
+    iter::position(param_iter_named(tree, name), ident.trim_start_matches('.'))
+}
+
 /// Returns the value for the given identifier
 pub fn get_value(mut tree: &dyn Tree, ident: &str) -> Option<value::Value> {
     let mut segments = ident.trim_start_matches('.').split('.');
@@ -139,3 +243,57 @@ impl_node!(
     I32(i32),
     F32(f32),
 );
+
+#[cfg(feature = "ext")]
+impl_node!(U64(u64), I64(i64), F64(f64));
+
+// Used by the derive of `Node` for enums with a 64-bit `#[repr(..)]`, which need the `ext` feature
+#[doc(hidden)]
+#[cfg(feature = "ext")]
+#[macro_export]
+macro_rules! __require_ext {
+    ($($item:tt)*) => {
+        $($item)*
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "ext"))]
+#[macro_export]
+macro_rules! __require_ext {
+    ($($item:tt)*) => {
+        compile_error!("enums with a 64-bit #[repr(..)] require the `ext` feature of mav-param");
+    };
+}
+
+// Booleans are not supported by Mavlink, so they are exposed as a `U8` of either 0 or 1
+impl value::Validated for bool {
+    fn value(&self) -> value::Value {
+        value::Value::U8(u8::from(*self))
+    }
+
+    fn assign(&mut self, value: value::Value) -> Result<(), Error> {
+        *self = match value {
+            value::Value::U8(0) => false,
+            value::Value::U8(1) => true,
+            value::Value::U8(_) => return Err(Error::InvalidValue),
+            _ => {
+                return Err(Error::TypeMismatch {
+                    expected: value::ParamType::Uint8,
+                    received: value.param_type(),
+                });
+            }
+        };
+        Ok(())
+    }
+}
+
+impl Node for bool {
+    fn node_ref(&self) -> NodeRef<'_> {
+        NodeRef::Value(value::Validated::value(self))
+    }
+
+    fn node_mut(&mut self) -> NodeMut<'_> {
+        NodeMut::Value(value::ValueMut::Validated(self))
+    }
+}
@@ -0,0 +1,326 @@
+use mavlink::dialects::common::{
+    MavParamType, PARAM_REQUEST_LIST_DATA, PARAM_REQUEST_READ_DATA, PARAM_SET_DATA,
+    PARAM_VALUE_DATA,
+};
+use mavlink::types::CharArray;
+
+use crate::server::{Request, Response};
+use crate::{Encoding, Error, Ident, ParamType, Parameter, Value};
+
+impl From<ParamType> for MavParamType {
+    fn from(param_type: ParamType) -> Self {
+        match param_type {
+            ParamType::Uint8 => MavParamType::MAV_PARAM_TYPE_UINT8,
+            ParamType::Int8 => MavParamType::MAV_PARAM_TYPE_INT8,
+            ParamType::Uint16 => MavParamType::MAV_PARAM_TYPE_UINT16,
+            ParamType::Int16 => MavParamType::MAV_PARAM_TYPE_INT16,
+            ParamType::Uint32 => MavParamType::MAV_PARAM_TYPE_UINT32,
+            ParamType::Int32 => MavParamType::MAV_PARAM_TYPE_INT32,
+            ParamType::Uint64 => MavParamType::MAV_PARAM_TYPE_UINT64,
+            ParamType::Int64 => MavParamType::MAV_PARAM_TYPE_INT64,
+            ParamType::Real32 => MavParamType::MAV_PARAM_TYPE_REAL32,
+            ParamType::Real64 => MavParamType::MAV_PARAM_TYPE_REAL64,
+        }
+    }
+}
+
+impl From<MavParamType> for ParamType {
+    fn from(param_type: MavParamType) -> Self {
+        match param_type {
+            MavParamType::MAV_PARAM_TYPE_UINT8 => ParamType::Uint8,
+            MavParamType::MAV_PARAM_TYPE_INT8 => ParamType::Int8,
+            MavParamType::MAV_PARAM_TYPE_UINT16 => ParamType::Uint16,
+            MavParamType::MAV_PARAM_TYPE_INT16 => ParamType::Int16,
+            MavParamType::MAV_PARAM_TYPE_UINT32 => ParamType::Uint32,
+            MavParamType::MAV_PARAM_TYPE_INT32 => ParamType::Int32,
+            MavParamType::MAV_PARAM_TYPE_UINT64 => ParamType::Uint64,
+            MavParamType::MAV_PARAM_TYPE_INT64 => ParamType::Int64,
+            MavParamType::MAV_PARAM_TYPE_REAL32 => ParamType::Real32,
+            MavParamType::MAV_PARAM_TYPE_REAL64 => ParamType::Real64,
+        }
+    }
+}
+
+impl From<&Ident> for CharArray<16> {
+    fn from(ident: &Ident) -> Self {
+        CharArray::new(*ident.as_raw())
+    }
+}
+
+impl TryFrom<&CharArray<16>> for Ident {
+    type Error = Error;
+
+    fn try_from(param_id: &CharArray<16>) -> Result<Self, Self::Error> {
+        Ident::try_from(&**param_id)
+    }
+}
+
+impl TryFrom<&PARAM_SET_DATA> for Value {
+    type Error = Error;
+
+    /// Decode the bytewise-encoded value, see [`Value::from_wire`].
+    fn try_from(data: &PARAM_SET_DATA) -> Result<Self, Self::Error> {
+        Value::from_wire(data.param_value, data.param_type.into())
+    }
+}
+
+impl TryFrom<&PARAM_SET_DATA> for Parameter {
+    type Error = Error;
+
+    fn try_from(data: &PARAM_SET_DATA) -> Result<Self, Self::Error> {
+        Ok(Parameter {
+            ident: Ident::try_from(&data.param_id)?,
+            value: Value::try_from(data)?,
+        })
+    }
+}
+
+impl TryFrom<&PARAM_SET_DATA> for Request {
+    type Error = Error;
+
+    fn try_from(data: &PARAM_SET_DATA) -> Result<Self, Self::Error> {
+        let Parameter { ident, value } = Parameter::try_from(data)?;
+        Ok(Request::Set {
+            param_id: ident,
+            value,
+        })
+    }
+}
+
+impl TryFrom<&PARAM_REQUEST_READ_DATA> for Request {
+    type Error = Error;
+
+    fn try_from(data: &PARAM_REQUEST_READ_DATA) -> Result<Self, Self::Error> {
+        Ok(Request::Read {
+            param_id: Ident::try_from(&data.param_id)?,
+            param_index: data.param_index,
+        })
+    }
+}
+
+impl From<&PARAM_REQUEST_LIST_DATA> for Request {
+    fn from(_: &PARAM_REQUEST_LIST_DATA) -> Self {
+        Request::List
+    }
+}
+
+// The ground-side conversions leave the target system and component at zero,
+// so they must be set by the sender, e.g. using struct update syntax.
+
+impl TryFrom<&Parameter> for PARAM_SET_DATA {
+    type Error = Error;
+
+    /// Encode the parameter using the bytewise encoding.
+    ///
+    /// Returns [`Error::OutOfRange`] if a 64-bit integer does not fit in 32 bits.
+    fn try_from(param: &Parameter) -> Result<Self, Self::Error> {
+        Ok(PARAM_SET_DATA {
+            param_value: param.value.encode(Encoding::Bytewise)?,
+            param_id: (&param.ident).into(),
+            param_type: param.value.param_type().into(),
+            ..Default::default()
+        })
+    }
+}
+
+impl TryFrom<&Request> for PARAM_REQUEST_READ_DATA {
+    type Error = Error;
+
+    /// Returns [`Error::UnsupportedMessage`] if the request is not a [`Request::Read`].
+    fn try_from(request: &Request) -> Result<Self, Self::Error> {
+        let Request::Read {
+            param_id,
+            param_index,
+        } = request
+        else {
+            return Err(Error::UnsupportedMessage);
+        };
+
+        Ok(PARAM_REQUEST_READ_DATA {
+            param_index: *param_index,
+            param_id: param_id.into(),
+            ..Default::default()
+        })
+    }
+}
+
+impl TryFrom<&Request> for PARAM_REQUEST_LIST_DATA {
+    type Error = Error;
+
+    /// Returns [`Error::UnsupportedMessage`] if the request is not a [`Request::List`].
+    fn try_from(request: &Request) -> Result<Self, Self::Error> {
+        match request {
+            Request::List => Ok(PARAM_REQUEST_LIST_DATA::default()),
+            _ => Err(Error::UnsupportedMessage),
+        }
+    }
+}
+
+impl TryFrom<&PARAM_VALUE_DATA> for Value {
+    type Error = Error;
+
+    /// Decode the bytewise-encoded value, see [`Value::from_wire`].
+    fn try_from(data: &PARAM_VALUE_DATA) -> Result<Self, Self::Error> {
+        Value::from_wire(data.param_value, data.param_type.into())
+    }
+}
+
+impl TryFrom<&PARAM_VALUE_DATA> for Parameter {
+    type Error = Error;
+
+    fn try_from(data: &PARAM_VALUE_DATA) -> Result<Self, Self::Error> {
+        Ok(Parameter {
+            ident: Ident::try_from(&data.param_id)?,
+            value: Value::try_from(data)?,
+        })
+    }
+}
+
+impl TryFrom<&PARAM_VALUE_DATA> for Response {
+    type Error = Error;
+
+    fn try_from(data: &PARAM_VALUE_DATA) -> Result<Self, Self::Error> {
+        Ok(Response {
+            param: Parameter::try_from(data)?,
+            param_index: data.param_index,
+            param_count: data.param_count,
+        })
+    }
+}
+
+impl TryFrom<&Response> for PARAM_VALUE_DATA {
+    type Error = Error;
+
+    /// Encode the response using the bytewise encoding, see [`Response::param_value`].
+    fn try_from(response: &Response) -> Result<Self, Self::Error> {
+        Ok(PARAM_VALUE_DATA {
+            param_value: response.param_value()?,
+            param_count: response.param_count,
+            param_index: response.param_index,
+            param_id: (&response.param.ident).into(),
+            param_type: response.param_type().into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mavlink::dialects::common::{
+        MavParamType, PARAM_REQUEST_LIST_DATA, PARAM_REQUEST_READ_DATA, PARAM_SET_DATA,
+        PARAM_VALUE_DATA,
+    };
+    use mavlink::types::CharArray;
+
+    use crate::server::{Request, Response};
+    use crate::{Error, Ident, ParamType, Parameter, Value};
+
+    #[test]
+    fn param_type() {
+        for code in 1..=10 {
+            let param_type = ParamType::try_from(code).unwrap();
+            let mav_param_type = MavParamType::from(param_type);
+            assert_eq!(mav_param_type as u8, code);
+            assert_eq!(ParamType::from(mav_param_type), param_type);
+        }
+    }
+
+    #[test]
+    fn param_id() {
+        // A full-length identifier has no null-terminator
+        let ident = Ident::from_str_truncated("sixteen.chars.id");
+        let param_id = CharArray::from(&ident);
+        assert_eq!(param_id.to_str(), Ok("sixteen.chars.id"));
+        assert_eq!(Ident::try_from(&param_id), Ok(ident));
+
+        let param_id = CharArray::from("mav.id.sys");
+        assert_eq!(
+            Ident::try_from(&param_id),
+            Ok(Ident::from_str_truncated("mav.id.sys"))
+        );
+
+        let param_id = CharArray::new([0xFF; 16]);
+        assert_eq!(Ident::try_from(&param_id), Err(Error::SequenceNotUtf8));
+    }
+
+    #[test]
+    fn requests() {
+        let data = PARAM_SET_DATA {
+            param_value: Value::I16(-5).into_bytewise(),
+            param_id: "mav.trim".into(),
+            param_type: MavParamType::MAV_PARAM_TYPE_INT16,
+            ..Default::default()
+        };
+        assert_eq!(
+            Request::try_from(&data),
+            Ok(Request::Set {
+                param_id: Ident::from_str_truncated("mav.trim"),
+                value: Value::I16(-5),
+            })
+        );
+
+        let data = PARAM_SET_DATA {
+            param_id: CharArray::new([0xFF; 16]),
+            ..data
+        };
+        assert_eq!(Parameter::try_from(&data), Err(Error::SequenceNotUtf8));
+
+        let data = PARAM_REQUEST_READ_DATA {
+            param_index: -1,
+            param_id: "mav.trim".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            Request::try_from(&data),
+            Ok(Request::Read {
+                param_id: Ident::from_str_truncated("mav.trim"),
+                param_index: -1,
+            })
+        );
+    }
+
+    #[test]
+    fn value() {
+        let response = Response {
+            param: Parameter {
+                ident: Ident::from_str_truncated("mav.gain"),
+                value: Value::F32(1.5),
+            },
+            param_index: 3,
+            param_count: 4,
+        };
+
+        let data = PARAM_VALUE_DATA::try_from(&response).unwrap();
+        assert!((data.param_value - 1.5).abs() < f32::EPSILON);
+        assert_eq!(data.param_type, MavParamType::MAV_PARAM_TYPE_REAL32);
+        assert_eq!(data.param_id.to_str(), Ok("mav.gain"));
+        assert_eq!(Response::try_from(&data), Ok(response));
+    }
+
+    #[test]
+    fn ground_side() {
+        let param = Parameter {
+            ident: Ident::from_str_truncated("mav.trim"),
+            value: Value::I16(-5),
+        };
+        let data = PARAM_SET_DATA::try_from(&param).unwrap();
+        assert_eq!(data.param_type, MavParamType::MAV_PARAM_TYPE_INT16);
+        assert_eq!(Parameter::try_from(&data), Ok(param));
+
+        let read = Request::Read {
+            param_id: Ident::from_str_truncated("mav.trim"),
+            param_index: -1,
+        };
+        let data = PARAM_REQUEST_READ_DATA::try_from(&read).unwrap();
+        assert_eq!(Request::try_from(&data), Ok(read.clone()));
+
+        let data = PARAM_REQUEST_LIST_DATA::try_from(&Request::List).unwrap();
+        assert_eq!(Request::from(&data), Request::List);
+
+        // Only the matching request can be converted
+        assert_eq!(
+            PARAM_REQUEST_READ_DATA::try_from(&Request::List),
+            Err(Error::UnsupportedMessage)
+        );
+        assert!(PARAM_REQUEST_LIST_DATA::try_from(&read).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+use heapless::Vec;
+
+use crate::server::{Request, Response};
+use crate::{Error, Ident, Parameter};
+
+/// A ground-side copy of the parameter list of a remote component.
+///
+/// The mirror is fed the `PARAM_VALUE` messages received from the component, in any order
+/// and with any number of them missing or duplicated. Each parameter is stored at its index,
+/// so the gaps can be requested again using [`ParamMirror::requests`] until the list is complete.
+///
+/// Up to `N` parameters can be stored.
+pub struct ParamMirror<const N: usize> {
+    params: Vec<Option<Parameter>, N>,
+    received: u16,
+}
+
+impl<const N: usize> Default for ParamMirror<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ParamMirror<N> {
+    /// Creates a new empty mirror.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            params: Vec::new(),
+            received: 0,
+        }
+    }
+
+    /// Store a received parameter.
+    ///
+    /// If the parameter count differs from what was previously received, the remote list is
+    /// assumed to have changed and all previously received parameters are dropped.
+    ///
+    /// Responses with an index outside the list, such as the `u16::MAX` index used by
+    /// some components when answering a `PARAM_SET`, are matched by identifier instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::CapacityExceeded`] if the remote list has more than `N` parameters,
+    /// and [`Error::NoSuchParameter`] if a response without a valid index cannot be matched.
+    pub fn receive(&mut self, response: &Response) -> Result<(), Error> {
+        if usize::from(response.param_count) != self.params.len() {
+            self.reset(response.param_count)?;
+        }
+
+        let slot = match self.params.get_mut(usize::from(response.param_index)) {
+            Some(slot) => slot,
+            None => self
+                .params
+                .iter_mut()
+                .find(|slot| {
+                    slot.as_ref()
+                        .is_some_and(|p| p.ident == response.param.ident)
+                })
+                .ok_or(Error::NoSuchParameter)?,
+        };
+
+        if slot.is_none() {
+            self.received += 1;
+        }
+
+        *slot = Some(response.param.clone());
+        Ok(())
+    }
+
+    /// Drop all received parameters.
+    pub fn clear(&mut self) {
+        self.params.clear();
+        self.received = 0;
+    }
+
+    /// The number of parameters reported by the remote component, if any were received
+    #[must_use]
+    pub fn param_count(&self) -> Option<u16> {
+        (!self.params.is_empty()).then_some(self.list_len())
+    }
+
+    /// The number of unique parameters received so far
+    #[must_use]
+    pub fn received(&self) -> u16 {
+        self.received
+    }
+
+    /// Returns `true` once every parameter of the remote list has been received
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        !self.params.is_empty() && self.received == self.list_len()
+    }
+
+    /// Iterate the indices of the parameters which have not been received yet
+    pub fn missing(&self) -> impl Iterator<Item = u16> + '_ {
+        self.params
+            .iter()
+            .zip(0..)
+            .filter_map(|(slot, index)| slot.is_none().then_some(index))
+    }
+
+    /// Iterate the requests needed to complete the mirror.
+    ///
+    /// Before anything is received this is a single [`Request::List`],
+    /// afterwards it is a [`Request::Read`] for every missing index.
+    ///
+    /// Note: Indices above `i16::MAX` cannot be requested by index, and the identifier of
+    /// a missing parameter is unknown. If any of those are missing, the whole list is
+    /// requested again instead, since it is the only way to receive them.
+    pub fn requests(&self) -> impl Iterator<Item = Request> + '_ {
+        let unaddressable = self.missing().any(|index| i16::try_from(index).is_err());
+        let list = (self.params.is_empty() || unaddressable).then_some(Request::List);
+        let reads = self
+            .missing()
+            .filter(move |_| !unaddressable)
+            .map(|index| Request::Read {
+                param_id: Ident::new(),
+                // All indices fit, since none are unaddressable
+                param_index: i16::try_from(index).unwrap_or(-1),
+            });
+
+        list.into_iter().chain(reads)
+    }
+
+    /// Returns the parameter at the given index, if it has been received
+    #[must_use]
+    pub fn get_by_index(&self, index: u16) -> Option<&Parameter> {
+        self.params.get(usize::from(index))?.as_ref()
+    }
+
+    /// Returns the parameter with the given identifier, if it has been received
+    #[must_use]
+    pub fn get(&self, ident: &str) -> Option<&Parameter> {
+        self.params
+            .iter()
+            .flatten()
+            .find(|param| param.ident.as_str() == ident)
+    }
+
+    /// Iterate all parameters in index order, once the mirror is complete
+    #[must_use]
+    pub fn params(&self) -> Option<impl Iterator<Item = &Parameter>> {
+        self.is_complete().then(|| self.params.iter().flatten())
+    }
+
+    fn reset(&mut self, count: u16) -> Result<(), Error> {
+        self.clear();
+        self.params
+            .resize(usize::from(count), None)
+            .map_err(|()| Error::CapacityExceeded)
+    }
+
+    fn list_len(&self) -> u16 {
+        // The list length is always set from a `u16` parameter count
+        u16::try_from(self.params.len()).unwrap_or(u16::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParamMirror;
+    use crate as mav_param;
+    use mav_param::server::{ParamServer, Request, Response};
+    use mav_param::{Error, Ident, Parameter, Tree, Value};
+
+    #[derive(Tree)]
+    struct Params {
+        id: (u8, u8),
+        gain: [f32; 4],
+        timeout: u16,
+    }
+
+    fn params() -> Params {
+        Params {
+            id: (1, 2),
+            gain: [0.5, 1.0, 1.5, 2.0],
+            timeout: 500,
+        }
+    }
+
+    fn list(server: &mut ParamServer) -> Vec<Response> {
+        assert_eq!(server.handle(&Request::List), Ok(None));
+        core::iter::from_fn(|| server.poll()).collect()
+    }
+
+    #[test]
+    fn shuffled_and_dropped() {
+        let mut params = params();
+        let mut server = ParamServer::new_named(&mut params, "mav");
+        let mut mirror = ParamMirror::<8>::new();
+
+        assert_eq!(mirror.requests().collect::<Vec<_>>(), [Request::List]);
+
+        // Deliver every other message in reverse, with one duplicate
+        let responses = list(&mut server);
+        for response in responses.iter().rev().step_by(2) {
+            mirror.receive(response).unwrap();
+        }
+        mirror.receive(&responses[0]).unwrap();
+
+        assert_eq!(mirror.param_count(), Some(7));
+        assert_eq!(mirror.received(), 4);
+        assert!(!mirror.is_complete());
+        assert!(mirror.params().is_none());
+        assert_eq!(mirror.missing().collect::<Vec<_>>(), [1, 3, 5]);
+
+        // Request the gaps
+        let requests: Vec<_> = mirror.requests().collect();
+        for request in &requests {
+            let response = server.handle(request).unwrap().unwrap();
+            mirror.receive(&response).unwrap();
+        }
+
+        assert!(mirror.is_complete());
+        assert_eq!(mirror.requests().count(), 0);
+        assert_eq!(
+            mirror.get("mav.gain.2").map(|p| p.value),
+            Some(Value::F32(1.5))
+        );
+
+        let mirrored: Vec<_> = mirror.params().unwrap().cloned().collect();
+        let expected: Vec<_> = mav_param::param_iter_named(&params, "mav")
+            .flatten()
+            .collect();
+        assert_eq!(mirrored, expected);
+    }
+
+    #[test]
+    fn updates() {
+        let mut params = params();
+        let mut server = ParamServer::new_named(&mut params, "mav");
+        let mut mirror = ParamMirror::<8>::new();
+
+        for response in list(&mut server) {
+            mirror.receive(&response).unwrap();
+        }
+
+        // A set acknowledged without a valid index is matched by identifier
+        let mut response = server
+            .handle(&Request::Set {
+                param_id: Ident::from_str_truncated("mav.timeout"),
+                value: Value::U16(100),
+            })
+            .unwrap()
+            .unwrap();
+        response.param_index = u16::MAX;
+        mirror.receive(&response).unwrap();
+        assert_eq!(
+            mirror.get_by_index(6).map(|p| p.value),
+            Some(Value::U16(100))
+        );
+
+        response.param.ident = Ident::from_str_truncated("mav.nothing");
+        assert_eq!(mirror.receive(&response), Err(Error::NoSuchParameter));
+
+        // A changed parameter count starts over
+        response.param_index = 0;
+        response.param_count = 2;
+        mirror.receive(&response).unwrap();
+        assert_eq!(mirror.param_count(), Some(2));
+        assert_eq!(mirror.received(), 1);
+        assert_eq!(mirror.missing().collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn capacity() {
+        let mut mirror = ParamMirror::<4>::new();
+        let response = Response {
+            param: Parameter {
+                ident: Ident::from_str_truncated("param"),
+                value: Value::U8(0),
+            },
+            param_index: 0,
+            param_count: 5,
+        };
+
+        assert_eq!(mirror.receive(&response), Err(Error::CapacityExceeded));
+        assert_eq!(mirror.param_count(), None);
+    }
+
+    #[test]
+    fn unaddressable() {
+        // The mirror is too large for the stack of the test thread
+        let test = || {
+            let mut mirror = ParamMirror::<{ 1 << 15 | 1 }>::new();
+            let mut response = Response {
+                param: Parameter {
+                    ident: Ident::from_str_truncated("param"),
+                    value: Value::U8(0),
+                },
+                param_index: 0,
+                param_count: 1 << 15 | 1,
+            };
+
+            let mut receive_all_but = |mirror: &mut ParamMirror<_>, skipped: u16| {
+                mirror.clear();
+                for index in (0..=1 << 15).filter(|index| *index != skipped) {
+                    response.param_index = index;
+                    mirror.receive(&response).unwrap();
+                }
+            };
+
+            // Indices which fit are requested by index
+            receive_all_but(&mut mirror, 5);
+            assert_eq!(
+                mirror.requests().collect::<Vec<_>>(),
+                [Request::Read {
+                    param_id: Ident::new(),
+                    param_index: 5,
+                }]
+            );
+
+            // The last index can only be received in another list
+            receive_all_but(&mut mirror, 1 << 15);
+            assert_eq!(mirror.requests().collect::<Vec<_>>(), [Request::List]);
+            assert!(!mirror.is_complete());
+        };
+
+        std::thread::Builder::new()
+            .stack_size(64 << 20)
+            .spawn(test)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+}
@@ -0,0 +1,254 @@
+use heapless::Vec;
+
+use crate::server::{ParamServer, Request, Response};
+use crate::{Error, Tree};
+
+/// The component id which addresses all components of a system, `MAV_COMP_ID_ALL`.
+pub const COMPONENT_ALL: u8 = 0;
+
+/// The system id which addresses all systems, used by broadcasts.
+pub const SYSTEM_ALL: u8 = 0;
+
+/// Serves the parameters of several Mavlink components of the same system.
+///
+/// Each component is a [`ParamServer`] mounted under its component id, with a tree and
+/// "root" name of its own. Requests are dispatched to the components they target, and
+/// responses are paired with the component id they should be sent from.
+///
+/// Up to `N` components can be mounted.
+pub struct ParamRouter<'a, const N: usize> {
+    system_id: u8,
+    components: Vec<(u8, ParamServer<'a>), N>,
+}
+
+impl<'a, const N: usize> ParamRouter<'a, N> {
+    /// Create a new router without any components, for the given system id
+    #[must_use]
+    pub const fn new(system_id: u8) -> Self {
+        Self {
+            system_id,
+            components: Vec::new(),
+        }
+    }
+
+    /// Mount a tree with a "root" name as the given component.
+    ///
+    /// If the component is already mounted, it is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::CapacityExceeded`] if `N` components are already mounted.
+    pub fn mount(
+        &mut self,
+        component_id: u8,
+        name: &'a str,
+        tree: &'a mut dyn Tree,
+    ) -> Result<(), Error> {
+        self.mount_server(component_id, ParamServer::new_named(tree, name))
+    }
+
+    /// Mount an already configured server as the given component.
+    ///
+    /// If the component is already mounted, it is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::CapacityExceeded`] if `N` components are already mounted.
+    pub fn mount_server(&mut self, component_id: u8, server: ParamServer<'a>) -> Result<(), Error> {
+        match self.server_mut(component_id) {
+            Some(mounted) => {
+                *mounted = server;
+                Ok(())
+            }
+            None => self
+                .components
+                .push((component_id, server))
+                .map_err(|_| Error::CapacityExceeded),
+        }
+    }
+
+    /// The ids of all mounted components
+    pub fn components(&self) -> impl Iterator<Item = u8> + '_ {
+        self.components.iter().map(|(id, _)| *id)
+    }
+
+    /// The number of parameters of the given component
+    #[must_use]
+    pub fn param_count(&self, component_id: u8) -> Option<u16> {
+        self.components
+            .iter()
+            .find(|(id, _)| *id == component_id)
+            .map(|(_, server)| server.param_count())
+    }
+
+    /// The server of the given component, e.g. to set parameters locally
+    pub fn server_mut(&mut self, component_id: u8) -> Option<&mut ParamServer<'a>> {
+        self.components
+            .iter_mut()
+            .find(|(id, _)| *id == component_id)
+            .map(|(_, server)| server)
+    }
+
+    /// Returns `true` while any component is still serving a list request
+    #[must_use]
+    pub fn is_listing(&self) -> bool {
+        self.components
+            .iter()
+            .any(|(_, server)| server.is_listing())
+    }
+
+    /// Handle an incoming request, addressed by the target system and component of the message.
+    ///
+    /// Yields the result of [`ParamServer::handle`] for every targeted component, paired with
+    /// its component id. Requests for [`COMPONENT_ALL`] are handled by all components, while
+    /// requests for another system are ignored.
+    ///
+    /// Note: The request is only handled by the components which are taken from the iterator.
+    pub fn handle<'r>(
+        &'r mut self,
+        target_system: u8,
+        target_component: u8,
+        request: &'r Request,
+    ) -> impl Iterator<Item = (u8, Result<Option<Response>, Error>)> + 'r {
+        let system = target_system == SYSTEM_ALL || target_system == self.system_id;
+
+        self.components
+            .iter_mut()
+            .filter(move |(id, _)| {
+                system && (target_component == COMPONENT_ALL || target_component == *id)
+            })
+            .map(move |(id, server)| (*id, server.handle(request)))
+    }
+
+    /// Retrieve the next response of an ongoing list request, paired with its component id.
+    ///
+    /// Components are listed one after another, in the order they were mounted.
+    pub fn poll(&mut self) -> Option<(u8, Response)> {
+        self.components
+            .iter_mut()
+            .find_map(|(id, server)| Some((*id, server.poll()?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{COMPONENT_ALL, ParamRouter};
+    use crate as mav_param;
+    use mav_param::server::{ParamServer, Request};
+    use mav_param::{Error, Ident, Tree, Value};
+
+    #[derive(Tree, Default)]
+    struct Autopilot {
+        rate: u16,
+        gain: f32,
+    }
+
+    #[derive(Tree, Default)]
+    struct Gimbal {
+        pitch: (i16, i16),
+        rate: u16,
+    }
+
+    fn read(ident: &str) -> Request {
+        Request::Read {
+            param_id: Ident::from_str_truncated(ident),
+            param_index: -1,
+        }
+    }
+
+    #[test]
+    fn dispatch() {
+        let (mut autopilot, mut gimbal) = (Autopilot::default(), Gimbal::default());
+
+        // The trees are borrowed until the router is dropped
+        {
+            let mut router = ParamRouter::<4>::new(1);
+            router.mount(1, "ap", &mut autopilot).unwrap();
+            router.mount(154, "gmb", &mut gimbal).unwrap();
+
+            assert_eq!(router.components().collect::<Vec<_>>(), [1, 154]);
+            assert_eq!(router.param_count(1), Some(2));
+            assert_eq!(router.param_count(154), Some(3));
+            assert_eq!(router.param_count(2), None);
+
+            let set = Request::Set {
+                param_id: Ident::from_str_truncated("gmb.rate"),
+                value: Value::U16(50),
+            };
+            let results: Vec<_> = router.handle(1, 154, &set).collect();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].0, 154);
+            assert_eq!(
+                results[0].1.as_ref().unwrap().as_ref().unwrap().param_index,
+                2
+            );
+
+            // Other systems and unknown components are ignored
+            assert_eq!(router.handle(2, 154, &set).count(), 0);
+            assert_eq!(router.handle(1, 2, &set).count(), 0);
+
+            // All components handle the request, though only one has the parameter
+            let results: Vec<_> = router.handle(0, COMPONENT_ALL, &read("gmb.rate")).collect();
+            assert_eq!(results[0], (1, Err(Error::NoSuchParameter)));
+            assert_eq!(
+                results[1].1.as_ref().unwrap().as_ref().unwrap().param.value,
+                Value::U16(50)
+            );
+
+            router
+                .server_mut(1)
+                .unwrap()
+                .set_local("ap.rate", Value::U16(400))
+                .unwrap();
+        }
+
+        assert_eq!(gimbal.rate, 50);
+        assert_eq!(autopilot.rate, 400);
+    }
+
+    #[test]
+    fn list() {
+        let (mut autopilot, mut gimbal) = (Autopilot::default(), Gimbal::default());
+        let mut router = ParamRouter::<2>::new(1);
+        router.mount(1, "ap", &mut autopilot).unwrap();
+        router.mount(154, "gmb", &mut gimbal).unwrap();
+
+        let results: Vec<_> = router.handle(1, COMPONENT_ALL, &Request::List).collect();
+        assert_eq!(results, [(1, Ok(None)), (154, Ok(None))]);
+        assert!(router.is_listing());
+
+        // Each component has its own indices and counts
+        let listed: Vec<_> = core::iter::from_fn(|| router.poll())
+            .map(|(id, r)| (id, r.param.ident, r.param_index, r.param_count))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                (1, Ident::from_str_truncated("ap.rate"), 0, 2),
+                (1, Ident::from_str_truncated("ap.gain"), 1, 2),
+                (154, Ident::from_str_truncated("gmb.pitch.0"), 0, 3),
+                (154, Ident::from_str_truncated("gmb.pitch.1"), 1, 3),
+                (154, Ident::from_str_truncated("gmb.rate"), 2, 3),
+            ]
+        );
+        assert!(!router.is_listing());
+    }
+
+    #[test]
+    fn mount() {
+        let (mut first, mut second) = (Autopilot::default(), Autopilot::default());
+        let mut gimbal = Gimbal::default();
+        let mut router = ParamRouter::<1>::new(1);
+
+        router.mount(1, "ap", &mut first).unwrap();
+        assert_eq!(
+            router.mount(154, "gmb", &mut gimbal),
+            Err(Error::CapacityExceeded)
+        );
+
+        // Mounting the same component again replaces it
+        let server = ParamServer::new_named(&mut second, "ap").with_hash_check();
+        router.mount_server(1, server).unwrap();
+        assert_eq!(router.param_count(1), Some(3));
+    }
+}
@@ -0,0 +1,679 @@
+use crate::hash::HASH_CHECK_IDENT;
+use crate::iter::ParamCursor;
+use crate::storage::{self, MavResult, Storage, StorageAction};
+use crate::transaction::{Transaction, TransactionError};
+use crate::value::Encoding;
+use crate::{
+    Error, Ident, ParamType, Parameter, Tree, Value, ValueMut, get_by_index, get_by_index_named,
+    get_value, get_value_mut, index_of, index_of_named, iter, param_iter, param_iter_named,
+    tree_hash,
+};
+
+/// A decoded request of the parameter protocol.
+///
+/// This is independent of any particular Mavlink library, so the transport
+/// layer is responsible for decoding the incoming messages into this type.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Request {
+    /// Corresponds to `PARAM_REQUEST_LIST`, asking for all parameters to be sent.
+    List,
+    /// Corresponds to `PARAM_REQUEST_READ`. If `param_index` is `-1` the
+    /// parameter is looked up using `param_id`, otherwise by its index.
+    Read { param_id: Ident, param_index: i16 },
+    /// Corresponds to `PARAM_SET`. The `value` must already be decoded into
+    /// the correct type, e.g. using [`Value::from_wire`].
+    Set { param_id: Ident, value: Value },
+}
+
+/// An outgoing parameter, corresponds to `PARAM_VALUE`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Response {
+    pub param: Parameter,
+    pub param_index: u16,
+    pub param_count: u16,
+}
+
+impl Response {
+    /// Get the Mavlink-compatible bytewise representation of the value
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::OutOfRange`] if a 64-bit integer does not fit in 32 bits, and
+    /// [`Error::OutOfRange`] or [`Error::PrecisionLoss`] if a 64-bit float does not fit
+    /// in a `f32`, in which case it can only be sent using the extended protocol.
+    pub fn param_value(&self) -> Result<f32, Error> {
+        self.param.value.encode(Encoding::Bytewise)
+    }
+
+    /// Get the Mavlink parameter type of the value
+    #[must_use]
+    pub fn param_type(&self) -> ParamType {
+        self.param.value.param_type()
+    }
+}
+
+/// A transport-agnostic server for the Mavlink parameter protocol.
+///
+/// The server consumes decoded [`Request`]s and produces the [`Response`]s
+/// which should be sent back as `PARAM_VALUE` messages. Since a list request
+/// produces many responses, these are retrieved one by one using [`ParamServer::poll`],
+/// allowing the caller to pace the outgoing messages.
+pub struct ParamServer<'a> {
+    tree: &'a mut dyn Tree,
+    name: Option<&'a str>,
+    // The number of parameters in the tree, which only changes with validated values
+    count: u16,
+    // Whether to append the tree hash as a synthetic parameter
+    hash_check: bool,
+    // The index and position of the next parameter to send for an ongoing list request
+    list: Option<(u16, ParamCursor)>,
+    // Called with every parameter changed through the server
+    change_hook: Option<&'a mut dyn FnMut(&Response)>,
+}
+
+impl<'a> ParamServer<'a> {
+    /// Create a new server for the given tree
+    pub fn new(tree: &'a mut dyn Tree) -> Self {
+        Self::with_name(tree, None)
+    }
+
+    /// Create a new server for the given tree with a "root" name defined
+    pub fn new_named(tree: &'a mut dyn Tree, name: &'a str) -> Self {
+        Self::with_name(tree, Some(name))
+    }
+
+    fn with_name(tree: &'a mut dyn Tree, name: Option<&'a str>) -> Self {
+        let mut server = Self {
+            tree,
+            name,
+            count: 0,
+            hash_check: false,
+            list: None,
+            change_hook: None,
+        };
+
+        server.count = iter::count(server.params());
+        server
+    }
+
+    /// Append the tree hash as a read-only `INT32` parameter `_HASH_CHECK` at the end of the list.
+    ///
+    /// This allows ground stations which support it to skip downloading the list, if the
+    /// hash matches their cached list. See [`crate::tree_hash`] for details.
+    #[must_use]
+    pub fn with_hash_check(mut self) -> Self {
+        self.hash_check = true;
+        self
+    }
+
+    /// Call the hook with the new value of every parameter changed through this server.
+    ///
+    /// The Mavlink parameter protocol requires that changes are broadcast as `PARAM_VALUE`,
+    /// so all ground stations stay in sync. The hook is called for set requests and
+    /// [`ParamServer::set_local`], as well as for the bulk changes of [`ParamServer::load`],
+    /// [`ParamServer::reset`] and [`ParamServer::commit`], though only if the value actually
+    /// changed bitwise. Since the response to a set request must be broadcast as well, it may
+    /// be sent by the hook alone.
+    #[must_use]
+    pub fn with_change_hook(mut self, hook: &'a mut dyn FnMut(&Response)) -> Self {
+        self.change_hook = Some(hook);
+        self
+    }
+
+    /// The number of parameters served
+    #[must_use]
+    pub fn param_count(&self) -> u16 {
+        self.count.saturating_add(self.hash_check.into())
+    }
+
+    /// Returns `true` while a list request is still being served
+    #[must_use]
+    pub fn is_listing(&self) -> bool {
+        self.list.is_some()
+    }
+
+    /// Handle an incoming request.
+    ///
+    /// Read and set requests are answered immediately, while a list request
+    /// returns `Ok(None)` and starts producing responses through [`ParamServer::poll`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoSuchParameter`] if the requested parameter does not exist,
+    /// [`Error::TypeMismatch`] if a set request has the wrong value type, and
+    /// [`Error::ReadOnly`] if a set request targets the `_HASH_CHECK` parameter.
+    pub fn handle(&mut self, request: &Request) -> Result<Option<Response>, Error> {
+        match request {
+            Request::List => {
+                self.list = Some((0, ParamCursor::new(self.name)));
+                Ok(None)
+            }
+            Request::Read {
+                param_id,
+                param_index,
+            } => {
+                let response = match u16::try_from(*param_index) {
+                    Ok(index) => self.read_index(index),
+                    Err(_) => self.read_ident(param_id),
+                };
+
+                response.map(Some).ok_or(Error::NoSuchParameter)
+            }
+            Request::Set { param_id, value } => self.set(param_id, *value).map(Some),
+        }
+    }
+
+    /// Set a parameter from the component itself, notifying the change hook if it changed.
+    ///
+    /// Note: The identifier must include the "root" name, like those of a set request.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as a set request through [`ParamServer::handle`], and
+    /// [`Error::SequenceTooLong`] if the identifier is longer than 16 bytes.
+    pub fn set_local(&mut self, ident: &str, value: Value) -> Result<Response, Error> {
+        let ident = Ident::try_from(ident.as_bytes())?;
+        self.set(&ident, value)
+    }
+
+    /// Load the stored parameters like [`storage::load`], notifying the change hook
+    /// of every parameter which changed. Returns the number of parameters applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the storage. Parameters loaded until the error remain applied.
+    pub fn load<S: Storage>(&mut self, storage: &mut S) -> Result<usize, S::Error> {
+        let mut applied = 0;
+        storage.load(&mut |param| {
+            if self.set_path(&param).is_ok() {
+                applied += 1;
+            }
+        })?;
+
+        Ok(applied)
+    }
+
+    /// Reset the parameters like [`storage::reset`], notifying the change hook
+    /// of every parameter which changed. Returns the number of parameters reset.
+    pub fn reset(&mut self, defaults: &dyn Tree) -> usize {
+        param_iter(defaults)
+            .flatten()
+            .filter(|param| self.set_path(param).is_ok())
+            .count()
+    }
+
+    /// Handle `MAV_CMD_PREFLIGHT_STORAGE` like [`storage::preflight_storage`],
+    /// notifying the change hook of every parameter which changed.
+    pub fn preflight_storage<S: Storage>(
+        &mut self,
+        storage: &mut S,
+        defaults: &dyn Tree,
+        action: StorageAction,
+    ) -> MavResult {
+        let result = match action {
+            StorageAction::ReadPersistent => self.load(storage).map(|_| ()),
+            StorageAction::WritePersistent => storage::save(self.tree, storage),
+            StorageAction::ResetConfigDefault | StorageAction::ResetAllDefault => {
+                self.reset(defaults);
+                Ok(())
+            }
+            StorageAction::ResetSensorDefault => return MavResult::Unsupported,
+        };
+
+        match result {
+            Ok(()) => MavResult::Accepted,
+            Err(_) => MavResult::Failed,
+        }
+    }
+
+    /// Commit the transaction like [`Transaction::commit`], notifying the change hook
+    /// of every staged parameter which changed.
+    ///
+    /// # Errors
+    ///
+    /// See [`Transaction::commit`].
+    pub fn commit<const N: usize, const S: usize>(
+        &mut self,
+        transaction: &mut Transaction<N, S>,
+    ) -> Result<usize, TransactionError> {
+        let staged = transaction.pending().iter().map(|param| {
+            let previous = get_value(self.tree, param.ident.as_str());
+            (param.ident.clone(), previous)
+        });
+        let staged = staged.collect::<heapless::Vec<_, N>>();
+
+        let written = transaction.commit(self.tree)?;
+        self.count = iter::count(self.params());
+
+        for (path, previous) in staged {
+            if let Some(value) = get_value(self.tree, path.as_str())
+                && previous.is_none_or(|previous| !previous.bitwise_eq(value))
+                && let Some(ident) = self.rooted(path.as_str())
+                && let Some(response) = self.read_ident(&ident)
+                && let Some(hook) = self.change_hook.as_mut()
+            {
+                hook(&response);
+            }
+        }
+
+        Ok(written)
+    }
+
+    /// Retrieve the next response of an ongoing list request, if any.
+    pub fn poll(&mut self) -> Option<Response> {
+        let (index, mut cursor) = self.list.take()?;
+
+        // The cursor continues where the previous response left off, skipping errors
+        let response = match iter::next_param(&mut cursor, self.tree) {
+            Some(param) => Response {
+                param,
+                param_index: index,
+                param_count: self.param_count(),
+            },
+            None => self.read_index(index)?,
+        };
+
+        self.list = index.checked_add(1).map(|next| (next, cursor));
+        Some(response)
+    }
+
+    fn params(&self) -> iter::ParamIter<'_> {
+        match self.name {
+            Some(name) => param_iter_named(self.tree, name),
+            None => param_iter(self.tree),
+        }
+    }
+
+    fn read_index(&self, index: u16) -> Option<Response> {
+        let param = match self.name {
+            Some(name) => get_by_index_named(self.tree, name, index),
+            None => get_by_index(self.tree, index),
+        };
+        let param = match param {
+            Some(param) => param,
+            None if self.hash_check && index == self.count => Parameter {
+                ident: Ident::from_str_truncated(HASH_CHECK_IDENT),
+                value: Value::I32(tree_hash(self.tree, self.name).cast_signed()),
+            },
+            None => return None,
+        };
+
+        Some(Response {
+            param,
+            param_index: index,
+            param_count: self.param_count(),
+        })
+    }
+
+    pub(crate) fn read_ident(&self, ident: &Ident) -> Option<Response> {
+        if self.hash_check && ident.as_str() == HASH_CHECK_IDENT {
+            return self.read_index(self.count);
+        }
+
+        let param_index = match self.name {
+            Some(name) => index_of_named(self.tree, name, ident.as_str())?,
+            None => index_of(self.tree, ident.as_str())?,
+        };
+        // The index is known now, so the value is looked up directly rather than walking again
+        let value = get_value(self.tree, self.path(ident))?;
+
+        Some(Response {
+            param: Parameter {
+                ident: Ident::from_str_truncated(ident.as_str().trim_start_matches('.')),
+                value,
+            },
+            param_index,
+            param_count: self.param_count(),
+        })
+    }
+
+    pub(crate) fn set(&mut self, ident: &Ident, value: Value) -> Result<Response, Error> {
+        if self.hash_check && ident.as_str() == HASH_CHECK_IDENT {
+            return Err(Error::ReadOnly);
+        }
+
+        let mut response = self.read_ident(ident).ok_or(Error::NoSuchParameter)?;
+
+        let path = self.path(ident);
+        let mut value_mut = get_value_mut(self.tree, path).ok_or(Error::NoSuchParameter)?;
+
+        // Selecting the variant of an enum changes the parameter count
+        let reshapes = matches!(&value_mut, ValueMut::Validated(tag) if tag.selects_variant());
+        value_mut.assign(value)?;
+
+        if reshapes {
+            self.count = iter::count(self.params());
+            response.param_count = self.param_count();
+        }
+
+        let previous = core::mem::replace(&mut response.param.value, value);
+        // Values are compared bitwise, so e.g. a NaN which is set again is not a change
+        if !previous.bitwise_eq(value)
+            && let Some(hook) = self.change_hook.as_mut()
+        {
+            hook(&response);
+        }
+
+        Ok(response)
+    }
+
+    // Set a parameter whose identifier does not include the root name, like those in storage
+    fn set_path(&mut self, param: &Parameter) -> Result<Response, Error> {
+        let ident = self
+            .rooted(param.ident.as_str())
+            .ok_or(Error::SequenceTooLong)?;
+        self.set(&ident, param.value)
+    }
+
+    // Prefix the identifier with the root name, if any
+    // Strip the root name from an identifier, since the tree is not aware of it
+    fn path<'i>(&self, ident: &'i Ident) -> &'i str {
+        let ident = ident.as_str().trim_start_matches('.');
+        match self.name {
+            Some(name) => ident.strip_prefix(name).unwrap_or_default(),
+            None => ident,
+        }
+    }
+
+    fn rooted(&self, path: &str) -> Option<Ident> {
+        let Some(name) = self.name else {
+            return Ident::try_from(path.as_bytes()).ok();
+        };
+
+        let mut ident = Ident::try_from(name.as_bytes()).ok()?;
+        ident.push_entry(path).then_some(ident)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParamServer, Request, Response};
+    use crate as mav_param;
+    use mav_param::storage::{MavResult, MemoryStorage, StorageAction};
+    use mav_param::transaction::Transaction;
+    use mav_param::{Error, Ident, ParamType, Parameter, Tree, Value};
+
+    #[derive(Tree, Default)]
+    struct Params {
+        id: Id,
+        timeout: u16,
+        gain: f32,
+    }
+
+    #[derive(Tree, Default)]
+    struct Id {
+        sys: u8,
+        com: u8,
+    }
+
+    fn response(ident: &str, value: Value, param_index: u16) -> Response {
+        Response {
+            param: Parameter {
+                ident: Ident::from_str_truncated(ident),
+                value,
+            },
+            param_index,
+            param_count: 4,
+        }
+    }
+
+    #[test]
+    fn list() {
+        let mut params = Params::default();
+        let mut server = ParamServer::new_named(&mut params, "mav");
+
+        assert_eq!(server.poll(), None);
+        assert_eq!(server.handle(&Request::List), Ok(None));
+        assert!(server.is_listing());
+
+        let responses: Vec<_> = core::iter::from_fn(|| server.poll()).collect();
+        assert_eq!(
+            responses,
+            [
+                response("mav.id.sys", Value::U8(0), 0),
+                response("mav.id.com", Value::U8(0), 1),
+                response("mav.timeout", Value::U16(0), 2),
+                response("mav.gain", Value::F32(0.0), 3),
+            ]
+        );
+
+        assert!(!server.is_listing());
+    }
+
+    #[test]
+    fn read() {
+        let mut params = Params {
+            timeout: 500,
+            ..Default::default()
+        };
+        let mut server = ParamServer::new_named(&mut params, "mav");
+
+        let request = Request::Read {
+            param_id: Ident::from_str_truncated("mav.timeout"),
+            param_index: -1,
+        };
+        assert_eq!(
+            server.handle(&request),
+            Ok(Some(response("mav.timeout", Value::U16(500), 2)))
+        );
+
+        // The index takes precedence over the identifier
+        let request = Request::Read {
+            param_id: Ident::from_str_truncated("mav.timeout"),
+            param_index: 1,
+        };
+        assert_eq!(
+            server.handle(&request),
+            Ok(Some(response("mav.id.com", Value::U8(0), 1)))
+        );
+
+        let request = Request::Read {
+            param_id: Ident::from_str_truncated("mav.nothing"),
+            param_index: -1,
+        };
+        assert_eq!(server.handle(&request), Err(Error::NoSuchParameter));
+
+        let request = Request::Read {
+            param_id: Ident::new(),
+            param_index: 4,
+        };
+        assert_eq!(server.handle(&request), Err(Error::NoSuchParameter));
+    }
+
+    #[test]
+    fn set() {
+        let mut params = Params::default();
+        let mut server = ParamServer::new_named(&mut params, "mav");
+
+        let request = Request::Set {
+            param_id: Ident::from_str_truncated("mav.id.sys"),
+            value: Value::U8(42),
+        };
+        assert_eq!(
+            server.handle(&request),
+            Ok(Some(response("mav.id.sys", Value::U8(42), 0)))
+        );
+
+        // Type mismatch is rejected
+        let request = Request::Set {
+            param_id: Ident::from_str_truncated("mav.gain"),
+            value: Value::U32(1),
+        };
+        assert_eq!(
+            server.handle(&request),
+            Err(Error::TypeMismatch {
+                expected: ParamType::Real32,
+                received: ParamType::Uint32,
+            })
+        );
+
+        // Unknown parameters are rejected
+        let request = Request::Set {
+            param_id: Ident::from_str_truncated("id.sys"),
+            value: Value::U8(1),
+        };
+        assert_eq!(server.handle(&request), Err(Error::NoSuchParameter));
+
+        assert_eq!(params.id.sys, 42);
+        assert!(params.gain.abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn hash_check() {
+        let mut params = Params::default();
+        let hash = mav_param::tree_hash(&params, Some("mav"));
+        let mut server = ParamServer::new_named(&mut params, "mav").with_hash_check();
+        assert_eq!(server.param_count(), 5);
+
+        let hash_response = Response {
+            param: Parameter {
+                ident: Ident::from_str_truncated("_HASH_CHECK"),
+                value: Value::I32(hash.cast_signed()),
+            },
+            param_index: 4,
+            param_count: 5,
+        };
+
+        server.handle(&Request::List).unwrap();
+        let responses: Vec<_> = core::iter::from_fn(|| server.poll()).collect();
+        assert_eq!(responses.len(), 5);
+        assert!(responses.iter().all(|response| response.param_count == 5));
+        assert_eq!(responses[4], hash_response);
+
+        let request = Request::Read {
+            param_id: Ident::from_str_truncated("_HASH_CHECK"),
+            param_index: -1,
+        };
+        assert_eq!(server.handle(&request), Ok(Some(hash_response)));
+
+        let request = Request::Set {
+            param_id: Ident::from_str_truncated("_HASH_CHECK"),
+            value: Value::U32(0),
+        };
+        assert_eq!(server.handle(&request), Err(Error::ReadOnly));
+
+        // The hash follows changes to the tree
+        let request = Request::Set {
+            param_id: Ident::from_str_truncated("mav.timeout"),
+            value: Value::U16(100),
+        };
+        server.handle(&request).unwrap();
+
+        let Some(response) = server
+            .handle(&Request::Read {
+                param_id: Ident::new(),
+                param_index: 4,
+            })
+            .unwrap()
+        else {
+            panic!("Expected a response")
+        };
+        assert_ne!(response.param.value, Value::U32(hash));
+    }
+
+    #[test]
+    fn variant_count() {
+        #[derive(Tree)]
+        enum Output {
+            Pwm { rate: u16 },
+            Dshot { rate: u16, telemetry: bool },
+        }
+
+        let mut output = Output::Pwm { rate: 400 };
+        let mut server = ParamServer::new(&mut output);
+        assert_eq!(server.param_count(), 2);
+
+        // Selecting another variant updates the count
+        let response = server.set_local("tag", Value::U8(1)).unwrap();
+        assert_eq!((response.param_count, server.param_count()), (3, 3));
+
+        let request = Request::Read {
+            param_id: Ident::new(),
+            param_index: 2,
+        };
+        let response = server.handle(&request).unwrap().unwrap();
+        assert_eq!(response.param.ident.as_str(), "telemetry");
+    }
+
+    #[test]
+    fn change_hook() {
+        let mut params = Params::default();
+        let mut changes = Vec::new();
+        let mut hook = |response: &Response| changes.push(response.clone());
+        let mut server = ParamServer::new_named(&mut params, "mav").with_change_hook(&mut hook);
+
+        let request = Request::Set {
+            param_id: Ident::from_str_truncated("mav.timeout"),
+            value: Value::U16(100),
+        };
+        server.handle(&request).unwrap();
+
+        // Setting the same value again is not a change
+        server.handle(&request).unwrap();
+
+        assert_eq!(
+            server.set_local("mav.id.com", Value::U8(7)),
+            Ok(response("mav.id.com", Value::U8(7), 1))
+        );
+        assert_eq!(
+            server.set_local("mav.gain", Value::U8(7)),
+            Err(Error::TypeMismatch {
+                expected: ParamType::Real32,
+                received: ParamType::Uint8,
+            })
+        );
+        assert_eq!(
+            server.set_local("mav.this.is.too.long", Value::U8(7)),
+            Err(Error::SequenceTooLong)
+        );
+
+        assert_eq!(
+            changes,
+            [
+                response("mav.timeout", Value::U16(100), 2),
+                response("mav.id.com", Value::U8(7), 1),
+            ]
+        );
+        assert_eq!(params.id.com, 7);
+    }
+
+    #[test]
+    fn hook_on_bulk_changes() {
+        let stored = Params {
+            timeout: 100,
+            gain: f32::NAN,
+            ..Default::default()
+        };
+        let mut storage = MemoryStorage::<8>::new();
+        mav_param::storage::save(&stored, &mut storage).unwrap();
+
+        let mut params = Params::default();
+        let mut changes = Vec::new();
+        let mut hook = |response: &Response| changes.push(response.param.ident.clone());
+        let mut server = ParamServer::new_named(&mut params, "mav").with_change_hook(&mut hook);
+
+        // Loading the same values again, including NaN, is not a change
+        assert_eq!(server.load(&mut storage), Ok(4));
+        assert_eq!(server.load(&mut storage), Ok(4));
+
+        let mut transaction = Transaction::<2>::new();
+        for (ident, value) in [("id.sys", 3), ("id.com", 0)] {
+            let ident = Ident::from_str_truncated(ident);
+            let value = Value::U8(value);
+            transaction.stage(Parameter { ident, value }).unwrap();
+        }
+        assert_eq!(server.commit(&mut transaction), Ok(2));
+
+        let action = StorageAction::ResetAllDefault;
+        let result = server.preflight_storage(&mut storage, &Params::default(), action);
+        assert_eq!(result, MavResult::Accepted);
+
+        drop(server);
+        let idents = ["timeout", "gain", "id.sys", "id.sys", "timeout", "gain"];
+        let expected =
+            idents.map(|ident| Ident::try_from(format!("mav.{ident}").as_bytes()).unwrap());
+        assert_eq!(changes, expected);
+    }
+}
@@ -0,0 +1,276 @@
+use embedded_storage::nor_flash::{
+    ErrorType, NorFlash, NorFlashErrorKind, ReadNorFlash, check_erase, check_read, check_write,
+};
+
+use crate::hash::Crc32;
+use crate::{Ident, ParamType, Parameter, Value};
+
+/// The error of a storage in flash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlashError<E> {
+    /// The flash device returned an error
+    Flash(E),
+    /// The parameters do not fit in the storage region
+    Full,
+    /// The storage regions are too small, not aligned to the erase size, or overlapping
+    InvalidRegion,
+}
+
+/// The length of a stored parameter record in bytes.
+///
+/// A record holds the type code, the null-padded identifier, the little-endian value
+/// padded to 8 bytes, and a CRC32 over all preceding bytes.
+pub const RECORD_LEN: usize = 32;
+
+// The record length as a flash offset
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) const RECORD_STEP: u32 = RECORD_LEN as u32;
+
+const TYPE: usize = 0;
+const IDENT: usize = 1;
+const VALUE: usize = 17;
+const CRC: usize = 28;
+
+// Erased flash reads as all ones
+pub(crate) const ERASED: u8 = 0xFF;
+
+/// The content of a record slot
+pub(crate) enum Record {
+    /// The slot has not been written since it was erased
+    Erased,
+    /// The slot was written, but does not hold a valid parameter, e.g. due to
+    /// an interrupted write or a type which is not supported by this build
+    Invalid,
+    Valid(Parameter),
+}
+
+pub(crate) fn encode_record(param: &Parameter) -> [u8; RECORD_LEN] {
+    let mut bytes = [0u8; RECORD_LEN];
+    bytes[VALUE..VALUE + 8].copy_from_slice(&param.value.le_bytes());
+    bytes[TYPE] = param.value.param_type() as u8;
+    bytes[IDENT..VALUE].copy_from_slice(param.ident.as_raw());
+
+    let mut crc = Crc32::new();
+    crc.update(&bytes[..CRC]);
+    bytes[CRC..].copy_from_slice(&crc.finish().to_le_bytes());
+    bytes
+}
+
+pub(crate) fn decode_record(bytes: &[u8; RECORD_LEN]) -> Record {
+    fn le<const N: usize>(bytes: &[u8; RECORD_LEN]) -> [u8; N] {
+        let mut le_bytes = [0u8; N];
+        le_bytes.copy_from_slice(&bytes[VALUE..VALUE + N]);
+        le_bytes
+    }
+
+    if bytes.iter().all(|byte| *byte == ERASED) {
+        return Record::Erased;
+    }
+
+    let mut crc = Crc32::new();
+    crc.update(&bytes[..CRC]);
+    if crc.finish().to_le_bytes() != bytes[CRC..] {
+        return Record::Invalid;
+    }
+
+    let Ok(param_type) = ParamType::try_from(bytes[TYPE]) else {
+        return Record::Invalid;
+    };
+
+    let value = match param_type {
+        ParamType::Uint8 => Value::U8(u8::from_le_bytes(le(bytes))),
+        ParamType::Int8 => Value::I8(i8::from_le_bytes(le(bytes))),
+        ParamType::Uint16 => Value::U16(u16::from_le_bytes(le(bytes))),
+        ParamType::Int16 => Value::I16(i16::from_le_bytes(le(bytes))),
+        ParamType::Uint32 => Value::U32(u32::from_le_bytes(le(bytes))),
+        ParamType::Int32 => Value::I32(i32::from_le_bytes(le(bytes))),
+        ParamType::Real32 => Value::F32(f32::from_le_bytes(le(bytes))),
+        #[cfg(feature = "ext")]
+        ParamType::Uint64 => Value::U64(u64::from_le_bytes(le(bytes))),
+        #[cfg(feature = "ext")]
+        ParamType::Int64 => Value::I64(i64::from_le_bytes(le(bytes))),
+        #[cfg(feature = "ext")]
+        ParamType::Real64 => Value::F64(f64::from_le_bytes(le(bytes))),
+        #[cfg(not(feature = "ext"))]
+        ParamType::Uint64 | ParamType::Int64 | ParamType::Real64 => return Record::Invalid,
+    };
+
+    match Ident::try_from(&bytes[IDENT..VALUE]) {
+        Ok(ident) => Record::Valid(Parameter { ident, value }),
+        Err(_) => Record::Invalid,
+    }
+}
+
+/// A RAM-backed [`NorFlash`] of `N` bytes, for testing storage without hardware.
+///
+/// Like NOR flash, bytes must be erased before they are written again, erasing
+/// is done in pages of 256 bytes, and writes must be aligned to 4 bytes.
+///
+/// A power loss can be simulated with [`RamFlash::set_write_limit`].
+#[derive(Debug, Clone)]
+pub struct RamFlash<const N: usize> {
+    bytes: [u8; N],
+    // The number of bytes read and written since creation
+    read: usize,
+    written: usize,
+    write_limit: Option<usize>,
+}
+
+impl<const N: usize> RamFlash<N> {
+    /// Create a new fully erased flash
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            bytes: [ERASED; N],
+            read: 0,
+            written: 0,
+            write_limit: None,
+        }
+    }
+
+    /// The number of bytes read since the flash was created
+    #[must_use]
+    pub fn bytes_read(&self) -> usize {
+        self.read
+    }
+
+    /// The number of bytes written since the flash was created
+    #[must_use]
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Cut off all writes once [`RamFlash::written`] reaches the limit, as if power was lost.
+    ///
+    /// The write in progress is only partially performed, after which it and
+    /// all following writes fail, until the limit is removed.
+    pub fn set_write_limit(&mut self, limit: Option<usize>) {
+        self.write_limit = limit;
+    }
+
+    /// The raw content of the flash
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.bytes
+    }
+
+    /// The raw content of the flash, e.g. to corrupt it
+    pub fn as_bytes_mut(&mut self) -> &mut [u8; N] {
+        &mut self.bytes
+    }
+}
+
+impl<const N: usize> Default for RamFlash<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ErrorType for RamFlash<N> {
+    type Error = NorFlashErrorKind;
+}
+
+impl<const N: usize> ReadNorFlash for RamFlash<N> {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        check_read(self, offset, bytes.len())?;
+        let offset = offset as usize;
+        bytes.copy_from_slice(&self.bytes[offset..offset + bytes.len()]);
+        self.read += bytes.len();
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<const N: usize> NorFlash for RamFlash<N> {
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = 256;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        check_erase(self, from, to)?;
+        self.bytes[from as usize..to as usize].fill(ERASED);
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Returns [`NorFlashErrorKind::Other`] if the bytes were not erased before,
+    /// or if the write limit is reached.
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        check_write(self, offset, bytes.len())?;
+        let target = &mut self.bytes[offset as usize..offset as usize + bytes.len()];
+
+        if target.iter().any(|byte| *byte != ERASED) {
+            return Err(NorFlashErrorKind::Other);
+        }
+
+        let allowed = self
+            .write_limit
+            .map_or(bytes.len(), |limit| limit.saturating_sub(self.written));
+
+        if allowed < bytes.len() {
+            target[..allowed].copy_from_slice(&bytes[..allowed]);
+            self.written += allowed;
+            return Err(NorFlashErrorKind::Other);
+        }
+
+        target.copy_from_slice(bytes);
+        self.written += bytes.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RECORD_LEN, RamFlash, Record, decode_record, encode_record};
+    use crate as mav_param;
+    use embedded_storage::nor_flash::{NorFlash, NorFlashErrorKind, ReadNorFlash};
+    use mav_param::{Ident, Parameter, Value};
+
+    #[test]
+    fn record() {
+        let param = Parameter {
+            ident: Ident::from_str_truncated("pid.roll.p"),
+            value: Value::I16(-300),
+        };
+
+        let mut bytes = encode_record(&param);
+        assert!(matches!(decode_record(&bytes), Record::Valid(decoded) if decoded == param));
+
+        bytes[20] ^= 1;
+        assert!(matches!(decode_record(&bytes), Record::Invalid));
+        assert!(matches!(decode_record(&[0xFF; RECORD_LEN]), Record::Erased));
+    }
+
+    #[test]
+    fn ram_flash() {
+        let mut flash = RamFlash::<512>::new();
+        let mut bytes = [0u8; 4];
+
+        flash.write(4, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(flash.write(4, &[1, 2, 3, 4]), Err(NorFlashErrorKind::Other));
+        assert_eq!(flash.write(2, &[0; 4]), Err(NorFlashErrorKind::NotAligned));
+        assert_eq!(flash.erase(0, 128), Err(NorFlashErrorKind::NotAligned));
+
+        flash.read(4, &mut bytes).unwrap();
+        assert_eq!(bytes, [1, 2, 3, 4]);
+
+        flash.erase(0, 256).unwrap();
+        flash.read(4, &mut bytes).unwrap();
+        assert_eq!(bytes, [0xFF; 4]);
+
+        // The write is cut off after 2 more bytes
+        assert_eq!(flash.written(), 4);
+        flash.set_write_limit(Some(6));
+        assert_eq!(flash.write(4, &[1, 2, 3, 4]), Err(NorFlashErrorKind::Other));
+        assert_eq!(flash.write(8, &[1, 2, 3, 4]), Err(NorFlashErrorKind::Other));
+        flash.read(4, &mut bytes).unwrap();
+        assert_eq!(bytes, [1, 2, 0xFF, 0xFF]);
+        assert_eq!(flash.written(), 6);
+    }
+}
@@ -0,0 +1,362 @@
+use core::ops::Range;
+
+use embedded_storage::nor_flash::NorFlash;
+use heapless::Vec;
+
+use super::Storage;
+use super::flash::{FlashError, RECORD_LEN, RECORD_STEP, Record, decode_record, encode_record};
+use crate::{Parameter, Tree, param_iter};
+
+/// A [`Storage`] which appends parameter records to a log in NOR flash.
+///
+/// Saving only appends the parameters whose value differs from the last stored one, so
+/// the flash is erased as rarely as possible. When the log is full, it is compacted by
+/// erasing the region and writing all parameters of the tree anew. Loading applies the
+/// records from oldest to newest, so the newest value of each parameter wins. Records
+/// with an invalid CRC, e.g. due to an interrupted write, are skipped.
+///
+/// Finding the parameters which changed compares up to `N` parameters with the log per read
+/// of it, so the log is read once per save if the tree has at most `N` parameters.
+///
+/// Note: Compaction erases the stored parameters before writing them again, so a power
+/// loss during compaction loses the stored parameters.
+pub struct LogStorage<F, const N: usize> {
+    flash: F,
+    range: Range<u32>,
+    // The offset of the first erased record, found on first use
+    head: Option<u32>,
+}
+
+impl<F: NorFlash, const N: usize> LogStorage<F, N> {
+    /// Create a log in the given region of the flash.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FlashError::InvalidRegion`] if the region is not aligned to the erase size
+    /// of the flash, or if it cannot hold at least one record.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`RECORD_LEN`] is not a multiple of the read and write sizes of the flash.
+    pub fn new(flash: F, range: Range<u32>) -> Result<Self, FlashError<F::Error>> {
+        assert!(
+            RECORD_LEN.is_multiple_of(F::READ_SIZE) && RECORD_LEN.is_multiple_of(F::WRITE_SIZE),
+            "the record length must be a multiple of the read and write sizes"
+        );
+
+        if !(range.start as usize).is_multiple_of(F::ERASE_SIZE)
+            || !(range.end as usize).is_multiple_of(F::ERASE_SIZE)
+            || range.end.saturating_sub(range.start) < RECORD_STEP
+        {
+            return Err(FlashError::InvalidRegion);
+        }
+
+        Ok(Self {
+            flash,
+            range,
+            head: None,
+        })
+    }
+
+    /// Release the flash device
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    /// The number of records which can be appended before the log is full
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the flash device.
+    pub fn free(&mut self) -> Result<usize, FlashError<F::Error>> {
+        let head = self.head()?;
+        Ok((self.range.end - head) as usize / RECORD_LEN)
+    }
+
+    /// Append a single parameter to the log, e.g. from the change hook of a server.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FlashError::Full`] if the log is full, in which case the log must be
+    /// compacted by [`Storage::save`], and the error of the flash device.
+    pub fn append(&mut self, param: &Parameter) -> Result<(), FlashError<F::Error>> {
+        let head = self.head()?;
+        if head + RECORD_STEP > self.range.end {
+            return Err(FlashError::Full);
+        }
+
+        // The head moves on even if the write fails, since the record may be partially written
+        self.head = Some(head + RECORD_STEP);
+        self.flash
+            .write(head, &encode_record(param))
+            .map_err(FlashError::Flash)
+    }
+
+    /// Erase all stored parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the flash device.
+    pub fn erase(&mut self) -> Result<(), FlashError<F::Error>> {
+        self.head = None;
+        self.flash
+            .erase(self.range.start, self.range.end)
+            .map_err(FlashError::Flash)?;
+        self.head = Some(self.range.start);
+        Ok(())
+    }
+
+    fn read(&mut self, offset: u32) -> Result<Record, FlashError<F::Error>> {
+        let mut bytes = [0u8; RECORD_LEN];
+        self.flash
+            .read(offset, &mut bytes)
+            .map_err(FlashError::Flash)?;
+        Ok(decode_record(&bytes))
+    }
+
+    fn records(&self) -> impl Iterator<Item = u32> + use<F, N> {
+        self.range.clone().step_by(RECORD_LEN)
+    }
+
+    fn head(&mut self) -> Result<u32, FlashError<F::Error>> {
+        if let Some(head) = self.head {
+            return Ok(head);
+        }
+
+        let mut head = self.range.end;
+        for offset in self.records() {
+            if matches!(self.read(offset)?, Record::Erased) {
+                head = offset;
+                break;
+            }
+        }
+
+        self.head = Some(head);
+        Ok(head)
+    }
+
+    // Whether the newest stored value of each parameter differs from it, found in a single read
+    fn stale(&mut self, params: &[Parameter]) -> Result<[bool; N], FlashError<F::Error>> {
+        let head = self.head()?;
+        let mut stale = [true; N];
+
+        for offset in self.records().take_while(|offset| *offset < head) {
+            if let Record::Valid(stored) = self.read(offset)?
+                && let Some(index) = params.iter().position(|p| p.ident == stored.ident)
+            {
+                stale[index] = !stored.value.bitwise_eq(params[index].value);
+            }
+        }
+
+        Ok(stale)
+    }
+
+    fn compact(&mut self, tree: &dyn Tree) -> Result<(), FlashError<F::Error>> {
+        self.erase()?;
+        param_iter(tree)
+            .flatten()
+            .try_for_each(|param| self.append(&param))
+    }
+}
+
+impl<F: NorFlash, const N: usize> Storage for LogStorage<F, N> {
+    type Error = FlashError<F::Error>;
+
+    /// Finding the parameters which changed requires reading the log once per `N` parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FlashError::Full`] if the parameters do not fit in the region even
+    /// after compaction, and the error of the flash device.
+    fn save(&mut self, tree: &dyn Tree) -> Result<(), Self::Error> {
+        let mut params = param_iter(tree).flatten();
+
+        loop {
+            let chunk = params.by_ref().take(N).collect::<Vec<_, N>>();
+            if chunk.is_empty() {
+                return Ok(());
+            }
+
+            let stale = self.stale(&chunk)?;
+            for (param, _) in chunk.iter().zip(stale).filter(|(_, stale)| *stale) {
+                match self.append(param) {
+                    Err(FlashError::Full) => return self.compact(tree),
+                    result => result?,
+                }
+            }
+        }
+    }
+
+    fn load(&mut self, apply: &mut dyn FnMut(Parameter)) -> Result<(), Self::Error> {
+        let head = self.head()?;
+
+        for offset in self.records().take_while(|offset| *offset < head) {
+            if let Record::Valid(param) = self.read(offset)? {
+                apply(param);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LogStorage;
+    use crate as mav_param;
+    use mav_param::storage::flash::{FlashError, RECORD_LEN, RamFlash};
+    use mav_param::storage::{Storage, load, save};
+    use mav_param::{Ident, Parameter, Tree, Value};
+
+    #[derive(Tree, Default, Clone, PartialEq, Debug)]
+    struct Params {
+        rate: u16,
+        pid: (f32, f32, f32),
+    }
+
+    #[derive(Tree, Default)]
+    struct Renamed {
+        rate: u32,
+        pid: (f32, f32),
+        gain: f32,
+    }
+
+    fn params() -> Params {
+        Params {
+            rate: 400,
+            pid: (1.0, 0.5, 0.0),
+        }
+    }
+
+    #[test]
+    fn append_only_changes() {
+        let mut storage = LogStorage::<_, 16>::new(RamFlash::<1024>::new(), 256..768).unwrap();
+        let mut params = params();
+        assert_eq!(storage.free(), Ok(16));
+
+        save(&params, &mut storage).unwrap();
+        assert_eq!(storage.free(), Ok(12));
+
+        // Only the changed parameter is appended
+        params.pid.1 = 0.75;
+        save(&params, &mut storage).unwrap();
+        save(&params, &mut storage).unwrap();
+        assert_eq!(storage.free(), Ok(11));
+
+        // The log is found again after a reboot
+        let mut storage = LogStorage::<_, 16>::new(storage.into_inner(), 256..768).unwrap();
+        let mut loaded = Params::default();
+        assert_eq!(load(&mut loaded, &mut storage), Ok(5));
+        assert_eq!(loaded, params);
+        assert_eq!(storage.free(), Ok(11));
+
+        // The region is not exceeded
+        let flash = storage.into_inner();
+        assert!(flash.as_bytes()[..256].iter().all(|byte| *byte == 0xFF));
+        assert!(flash.as_bytes()[768..].iter().all(|byte| *byte == 0xFF));
+    }
+
+    #[test]
+    fn changes_across_chunks() {
+        let mut storage = LogStorage::<_, 16>::new(RamFlash::<1024>::new(), 0..1024).unwrap();
+        let mut values = [[0u8; 10]; 2];
+        values[0][0] = 1;
+        save(&values, &mut storage).unwrap();
+        assert_eq!(storage.free(), Ok(12));
+
+        // Only the changed parameters of both chunks are appended
+        values[0][0] = 2;
+        values[1][9] = 3;
+        save(&values, &mut storage).unwrap();
+        save(&values, &mut storage).unwrap();
+        assert_eq!(storage.free(), Ok(10));
+
+        let mut loaded = [[0u8; 10]; 2];
+        assert_eq!(load(&mut loaded, &mut storage), Ok(22));
+        assert_eq!(loaded, values);
+    }
+
+    #[test]
+    fn reads_log_once() {
+        let mut values = [[0u8; 10]; 2];
+        let mut storage = LogStorage::<_, 32>::new(RamFlash::<1024>::new(), 0..1024).unwrap();
+        save(&values, &mut storage).unwrap();
+
+        // The head is known, so saving reads each of the 20 records once
+        let read = storage.flash.bytes_read();
+        values[1][7] = 1;
+        save(&values, &mut storage).unwrap();
+        assert_eq!(storage.flash.bytes_read() - read, 20 * RECORD_LEN);
+        assert_eq!(storage.free(), Ok(11));
+    }
+
+    #[test]
+    fn invalid_region() {
+        let new = |range| LogStorage::<_, 16>::new(RamFlash::<1024>::new(), range).err();
+        assert_eq!(new(0..1024), None);
+        assert_eq!(new(128..1024), Some(FlashError::InvalidRegion));
+        assert_eq!(new(0..1000), Some(FlashError::InvalidRegion));
+        assert_eq!(new(256..256), Some(FlashError::InvalidRegion));
+    }
+
+    #[test]
+    fn compaction() {
+        let mut storage = LogStorage::<_, 16>::new(RamFlash::<512>::new(), 0..256).unwrap();
+        let mut params = params();
+
+        for rate in 0..20 {
+            params.rate = rate;
+            save(&params, &mut storage).unwrap();
+        }
+
+        // Compacted to 4 records every 5 saves, with one record appended by the others
+        assert_eq!(storage.free(), Ok(0));
+
+        let mut loaded = Params::default();
+        load(&mut loaded, &mut storage).unwrap();
+        assert_eq!(loaded, params);
+
+        // Parameters which do not fit even after compaction
+        let mut storage = LogStorage::<_, 16>::new(RamFlash::<512>::new(), 0..256).unwrap();
+        let ident = Ident::from_str_truncated("rate");
+        for _ in 0..8 {
+            storage
+                .append(&Parameter {
+                    ident: ident.clone(),
+                    value: Value::U16(1),
+                })
+                .unwrap();
+        }
+        assert_eq!(
+            storage.append(&Parameter {
+                ident,
+                value: Value::U16(2),
+            }),
+            Err(FlashError::Full)
+        );
+        assert_eq!(save(&[[0u8; 3]; 3], &mut storage), Err(FlashError::Full));
+    }
+
+    #[test]
+    fn skips_invalid() {
+        let mut storage = LogStorage::<_, 16>::new(RamFlash::<256>::new(), 0..256).unwrap();
+        save(&Renamed::default(), &mut storage).unwrap();
+        storage.save(&params()).unwrap();
+
+        // The newest "pid.0" record is corrupted, e.g. by an interrupted write
+        let mut flash = storage.into_inner();
+        flash.as_bytes_mut()[5 * RECORD_LEN + 20] = 0;
+        let mut storage = LogStorage::<_, 16>::new(flash, 0..256).unwrap();
+
+        // "rate" has changed type, and "gain" no longer exists
+        let mut loaded = Params::default();
+        assert_eq!(load(&mut loaded, &mut storage), Ok(5));
+        assert_eq!(
+            loaded,
+            Params {
+                rate: 400,
+                pid: (0.0, 0.5, 0.0),
+            }
+        );
+    }
+}
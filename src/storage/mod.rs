@@ -0,0 +1,328 @@
+use heapless::Vec;
+
+use crate::{Error, Parameter, Tree, get_value_mut, param_iter};
+
+#[cfg(feature = "flash")]
+pub mod flash;
+#[cfg(feature = "flash")]
+pub mod log;
+#[cfg(feature = "flash")]
+pub mod snapshot;
+
+/// A persistent store of parameters, e.g. in flash or EEPROM.
+///
+/// Parameters are stored with identifiers without the "root" name, as yielded by [`param_iter`].
+pub trait Storage {
+    type Error;
+
+    /// Replace the stored parameters with those of the tree, as yielded by [`param_iter`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parameters could not be stored.
+    fn save(&mut self, tree: &dyn Tree) -> Result<(), Self::Error>;
+
+    /// Call `apply` with every stored parameter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored parameters could not be read.
+    fn load(&mut self, apply: &mut dyn FnMut(Parameter)) -> Result<(), Self::Error>;
+}
+
+/// The storage action of `MAV_CMD_PREFLIGHT_STORAGE`, corresponds to
+/// `PREFLIGHT_STORAGE_PARAMETER_ACTION`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum StorageAction {
+    /// Load the parameters from storage
+    ReadPersistent = 0,
+    /// Save the parameters to storage
+    WritePersistent = 1,
+    /// Reset the user configurable parameters to their defaults
+    ResetConfigDefault = 2,
+    /// Reset the sensor calibration parameters to their defaults
+    ResetSensorDefault = 3,
+    /// Reset all parameters to their defaults
+    ResetAllDefault = 4,
+}
+
+impl StorageAction {
+    /// Get the action from `param1` of the command
+    #[must_use]
+    pub fn from_param(param: f32) -> Option<Self> {
+        Some(match param {
+            0.0 => StorageAction::ReadPersistent,
+            1.0 => StorageAction::WritePersistent,
+            2.0 => StorageAction::ResetConfigDefault,
+            3.0 => StorageAction::ResetSensorDefault,
+            4.0 => StorageAction::ResetAllDefault,
+            _ => return None,
+        })
+    }
+}
+
+/// The outcome of a command, corresponds to `MAV_RESULT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum MavResult {
+    Accepted = 0,
+    TemporarilyRejected = 1,
+    Denied = 2,
+    Unsupported = 3,
+    Failed = 4,
+    InProgress = 5,
+    Cancelled = 6,
+}
+
+/// Save all parameters of the tree to storage.
+///
+/// # Errors
+///
+/// Returns the error of the storage.
+pub fn save<S: Storage>(tree: &dyn Tree, storage: &mut S) -> Result<(), S::Error> {
+    storage.save(tree)
+}
+
+/// Load the stored parameters into the tree, returning the number of parameters applied.
+///
+/// Stored parameters which no longer exist in the tree, or whose type has changed, are skipped.
+/// This allows the tree to change between firmware versions, without losing the stored values
+/// of the parameters which remain.
+///
+/// Note: Use [`crate::server::ParamServer::load`] to notify the change hook of a server.
+///
+/// # Errors
+///
+/// Returns the error of the storage. Parameters loaded until the error remain applied.
+pub fn load<S: Storage>(tree: &mut dyn Tree, storage: &mut S) -> Result<usize, S::Error> {
+    let mut applied = 0;
+
+    storage.load(&mut |param| {
+        if get_value_mut(tree, param.ident.as_str())
+            .is_some_and(|mut value_mut| value_mut.try_assign(param.value))
+        {
+            applied += 1;
+        }
+    })?;
+
+    Ok(applied)
+}
+
+/// Reset the parameters of the tree to those of another tree holding the defaults,
+/// such as `Params::default()`, returning the number of parameters reset.
+///
+/// Note: Use [`crate::server::ParamServer::reset`] to notify the change hook of a server.
+pub fn reset(tree: &mut dyn Tree, defaults: &dyn Tree) -> usize {
+    param_iter(defaults)
+        .flatten()
+        .filter(|param| {
+            get_value_mut(tree, param.ident.as_str())
+                .is_some_and(|mut value_mut| value_mut.try_assign(param.value))
+        })
+        .count()
+}
+
+/// Handle `MAV_CMD_PREFLIGHT_STORAGE` for the parameters of the tree.
+///
+/// The tree is not aware of which parameters hold sensor calibration, so
+/// [`StorageAction::ResetSensorDefault`] is unsupported, while both other reset
+/// actions reset all parameters. Resetting does not save the parameters to storage.
+///
+/// Note: Use [`crate::server::ParamServer::preflight_storage`] to notify the change hook of a server.
+pub fn preflight_storage<S: Storage>(
+    tree: &mut dyn Tree,
+    storage: &mut S,
+    defaults: &dyn Tree,
+    action: StorageAction,
+) -> MavResult {
+    let result = match action {
+        StorageAction::ReadPersistent => load(tree, storage).map(|_| ()),
+        StorageAction::WritePersistent => save(tree, storage),
+        StorageAction::ResetConfigDefault | StorageAction::ResetAllDefault => {
+            reset(tree, defaults);
+            Ok(())
+        }
+        StorageAction::ResetSensorDefault => return MavResult::Unsupported,
+    };
+
+    match result {
+        Ok(()) => MavResult::Accepted,
+        Err(_) => MavResult::Failed,
+    }
+}
+
+/// A [`Storage`] which keeps up to `N` parameters in memory, e.g. for testing.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage<const N: usize> {
+    params: Vec<Parameter, N>,
+}
+
+impl<const N: usize> MemoryStorage<N> {
+    /// Creates a new empty storage.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { params: Vec::new() }
+    }
+
+    /// The stored parameters
+    #[must_use]
+    pub fn params(&self) -> &[Parameter] {
+        &self.params
+    }
+}
+
+impl<const N: usize> Storage for MemoryStorage<N> {
+    type Error = Error;
+
+    /// # Errors
+    ///
+    /// Returns [`Error::CapacityExceeded`] if there are more than `N` parameters,
+    /// in which case the stored parameters are unchanged.
+    fn save(&mut self, tree: &dyn Tree) -> Result<(), Error> {
+        let mut stored = Vec::new();
+        for param in param_iter(tree).flatten() {
+            stored.push(param).map_err(|_| Error::CapacityExceeded)?;
+        }
+
+        self.params = stored;
+        Ok(())
+    }
+
+    fn load(&mut self, apply: &mut dyn FnMut(Parameter)) -> Result<(), Error> {
+        self.params.iter().cloned().for_each(apply);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MavResult, MemoryStorage, StorageAction, preflight_storage};
+    use crate as mav_param;
+    use mav_param::Tree;
+
+    #[derive(Tree, Clone, PartialEq, Debug)]
+    struct Params {
+        rate: u16,
+        pid: (f32, f32, f32),
+    }
+
+    #[derive(Tree)]
+    struct Stored {
+        rate: u16,
+        pid: (u32, u8, f32),
+        gain: f32,
+    }
+
+    impl Default for Params {
+        fn default() -> Self {
+            Params {
+                rate: 100,
+                pid: (1.0, 0.5, 0.0),
+            }
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let defaults = Params::default();
+        let mut params = Params::default();
+        let mut storage = MemoryStorage::<8>::new();
+
+        params.rate = 400;
+        let write = StorageAction::from_param(1.0).unwrap();
+        assert_eq!(
+            preflight_storage(&mut params, &mut storage, &defaults, write),
+            MavResult::Accepted
+        );
+        assert_eq!(storage.params().len(), 4);
+
+        params.rate = 0;
+        params.pid.1 = 0.0;
+        assert_eq!(
+            preflight_storage(
+                &mut params,
+                &mut storage,
+                &defaults,
+                StorageAction::ReadPersistent
+            ),
+            MavResult::Accepted
+        );
+        assert_eq!(
+            params,
+            Params {
+                rate: 400,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn load_skips_unknown() {
+        let mut params = Params::default();
+        let mut storage = MemoryStorage::<8>::new();
+
+        // Stored by an older firmware with different parameters
+        let stored = Stored {
+            rate: 200,
+            pid: (2, 0, 0.25),
+            gain: 1.0,
+        };
+        mav_param::storage::save(&stored, &mut storage).unwrap();
+
+        assert_eq!(mav_param::storage::load(&mut params, &mut storage), Ok(2));
+        assert_eq!(
+            params,
+            Params {
+                rate: 200,
+                pid: (1.0, 0.5, 0.25),
+            }
+        );
+    }
+
+    #[test]
+    fn reset_and_failure() {
+        let defaults = Params::default();
+        let mut params = Params {
+            rate: 5,
+            pid: (2.0, 2.0, 2.0),
+        };
+        let mut storage = MemoryStorage::<2>::new();
+
+        assert_eq!(
+            preflight_storage(
+                &mut params,
+                &mut storage,
+                &defaults,
+                StorageAction::ResetSensorDefault
+            ),
+            MavResult::Unsupported
+        );
+        assert_eq!(
+            preflight_storage(
+                &mut params,
+                &mut storage,
+                &defaults,
+                StorageAction::WritePersistent
+            ),
+            MavResult::Failed
+        );
+        assert!(storage.params().is_empty());
+
+        assert_eq!(
+            preflight_storage(
+                &mut params,
+                &mut storage,
+                &defaults,
+                StorageAction::ResetAllDefault
+            ),
+            MavResult::Accepted
+        );
+        assert_eq!(params, defaults);
+        assert_eq!(StorageAction::from_param(5.0), None);
+    }
+}
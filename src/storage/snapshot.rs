@@ -0,0 +1,332 @@
+use core::ops::Range;
+
+use embedded_storage::nor_flash::NorFlash;
+
+use super::Storage;
+use super::flash::{FlashError, RECORD_LEN, RECORD_STEP, Record, decode_record, encode_record};
+use crate::hash::Crc32;
+use crate::{Parameter, Tree, param_iter};
+
+// Marks the header of a snapshot
+const MAGIC: [u8; 4] = *b"MPSN";
+
+const SEQUENCE: usize = 4;
+const COUNT: usize = 8;
+const CRC: usize = RECORD_LEN - 4;
+
+/// A [`Storage`] which writes complete snapshots of the parameters to two slots in NOR flash.
+///
+/// Every save erases the slot which does not hold the newest snapshot, writes all parameters
+/// to it, and finally writes a header with an incremented sequence number and a CRC32 over the
+/// snapshot. Loading picks the valid snapshot with the highest sequence number. If a save is
+/// interrupted, e.g. by a power loss, the header of the new snapshot is missing or invalid,
+/// so the previous snapshot is loaded instead.
+///
+/// Each slot holds a header and one record of [`RECORD_LEN`] bytes per parameter.
+pub struct SnapshotStorage<F> {
+    flash: F,
+    slots: [Range<u32>; 2],
+}
+
+impl<F: NorFlash> SnapshotStorage<F> {
+    /// Create a storage using the two given regions of the flash as slots.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FlashError::InvalidRegion`] if a slot is not aligned to the erase size of
+    /// the flash, if a slot cannot hold a header and at least one record, or if the slots overlap.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`RECORD_LEN`] is not a multiple of the read and write sizes of the flash.
+    pub fn new(flash: F, slots: [Range<u32>; 2]) -> Result<Self, FlashError<F::Error>> {
+        assert!(
+            RECORD_LEN.is_multiple_of(F::READ_SIZE) && RECORD_LEN.is_multiple_of(F::WRITE_SIZE),
+            "the record length must be a multiple of the read and write sizes"
+        );
+
+        let valid = slots.iter().all(|slot| {
+            (slot.start as usize).is_multiple_of(F::ERASE_SIZE)
+                && (slot.end as usize).is_multiple_of(F::ERASE_SIZE)
+                && slot.end.saturating_sub(slot.start) >= 2 * RECORD_STEP
+        });
+
+        let [a, b] = &slots;
+        if !valid || (a.start < b.end && b.start < a.end) {
+            return Err(FlashError::InvalidRegion);
+        }
+
+        Ok(Self { flash, slots })
+    }
+
+    /// Release the flash device
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    /// The sequence number of the newest valid snapshot, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the flash device.
+    pub fn sequence(&mut self) -> Result<Option<u32>, FlashError<F::Error>> {
+        Ok(self.newest()?.map(|(_, header)| header.sequence))
+    }
+
+    // The slot and header of the newest valid snapshot
+    fn newest(&mut self) -> Result<Option<(usize, Header)>, FlashError<F::Error>> {
+        let mut newest: Option<(usize, Header)> = None;
+
+        for slot in 0..self.slots.len() {
+            let Some(header) = self.validate(slot)? else {
+                continue;
+            };
+
+            // The sequence numbers are compared as if they wrap around
+            if newest
+                .is_none_or(|(_, other)| header.sequence.wrapping_sub(other.sequence) < 1 << 31)
+            {
+                newest = Some((slot, header));
+            }
+        }
+
+        Ok(newest)
+    }
+
+    // The header of the slot, if it holds a complete snapshot
+    fn validate(&mut self, slot: usize) -> Result<Option<Header>, FlashError<F::Error>> {
+        let start = self.slots[slot].start;
+        let mut bytes = [0u8; RECORD_LEN];
+        self.read(start, &mut bytes)?;
+
+        let count = u32::from_le_bytes(field(&bytes, COUNT));
+        if bytes[..SEQUENCE] != MAGIC || count > self.capacity(slot) {
+            return Ok(None);
+        }
+
+        let mut crc = Crc32::new();
+        let mut record = [0u8; RECORD_LEN];
+        for offset in self.records(slot).take(count as usize) {
+            self.read(offset, &mut record)?;
+            crc.update(&record);
+        }
+
+        crc.update(&bytes[..CRC]);
+        if crc.finish().to_le_bytes() != bytes[CRC..] {
+            return Ok(None);
+        }
+
+        Ok(Some(Header {
+            sequence: u32::from_le_bytes(field(&bytes, SEQUENCE)),
+            count,
+        }))
+    }
+
+    fn read(
+        &mut self,
+        offset: u32,
+        bytes: &mut [u8; RECORD_LEN],
+    ) -> Result<(), FlashError<F::Error>> {
+        self.flash.read(offset, bytes).map_err(FlashError::Flash)
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8; RECORD_LEN]) -> Result<(), FlashError<F::Error>> {
+        self.flash.write(offset, bytes).map_err(FlashError::Flash)
+    }
+
+    // The number of records which fit in the slot, besides the header
+    fn capacity(&self, slot: usize) -> u32 {
+        let slot = &self.slots[slot];
+        (slot.end - slot.start) / RECORD_STEP - 1
+    }
+
+    // The offsets of the records of the slot, which follow the header
+    fn records(&self, slot: usize) -> impl Iterator<Item = u32> + use<F> {
+        let slot = &self.slots[slot];
+        (slot.start + RECORD_STEP..slot.end).step_by(RECORD_LEN)
+    }
+}
+
+impl<F: NorFlash> Storage for SnapshotStorage<F> {
+    type Error = FlashError<F::Error>;
+
+    /// # Errors
+    ///
+    /// Returns [`FlashError::Full`] if the parameters do not fit in the inactive slot, in which
+    /// case nothing is written, and the error of the flash device. The newest snapshot which
+    /// was completely written remains valid in either case.
+    fn save(&mut self, tree: &dyn Tree) -> Result<(), Self::Error> {
+        let newest = self.newest()?;
+        let slot = newest.map_or(0, |(slot, _)| 1 - slot);
+        let sequence = newest.map_or(0, |(_, header)| header.sequence.wrapping_add(1));
+
+        let count = param_iter(tree).flatten().count();
+        let count = u32::try_from(count).map_err(|_| FlashError::Full)?;
+        if count > self.capacity(slot) {
+            return Err(FlashError::Full);
+        }
+
+        let Range { start, end } = self.slots[slot];
+        self.flash.erase(start, end).map_err(FlashError::Flash)?;
+
+        let mut crc = Crc32::new();
+        for (offset, param) in self.records(slot).zip(param_iter(tree).flatten()) {
+            let record = encode_record(&param);
+            crc.update(&record);
+            self.write(offset, &record)?;
+        }
+
+        // The header is written last, which completes the snapshot
+        let mut header = [0u8; RECORD_LEN];
+        header[..SEQUENCE].copy_from_slice(&MAGIC);
+        header[SEQUENCE..COUNT].copy_from_slice(&sequence.to_le_bytes());
+        header[COUNT..COUNT + 4].copy_from_slice(&count.to_le_bytes());
+        crc.update(&header[..CRC]);
+        header[CRC..].copy_from_slice(&crc.finish().to_le_bytes());
+        self.write(start, &header)
+    }
+
+    fn load(&mut self, apply: &mut dyn FnMut(Parameter)) -> Result<(), Self::Error> {
+        let Some((slot, header)) = self.newest()? else {
+            return Ok(());
+        };
+
+        let mut bytes = [0u8; RECORD_LEN];
+        for offset in self.records(slot).take(header.count as usize) {
+            self.read(offset, &mut bytes)?;
+            if let Record::Valid(param) = decode_record(&bytes) {
+                apply(param);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Header {
+    sequence: u32,
+    count: u32,
+}
+
+fn field(bytes: &[u8; RECORD_LEN], at: usize) -> [u8; 4] {
+    let mut field = [0u8; 4];
+    field.copy_from_slice(&bytes[at..at + 4]);
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SnapshotStorage;
+    use crate as mav_param;
+    use mav_param::Tree;
+    use mav_param::storage::flash::{FlashError, RamFlash};
+    use mav_param::storage::{load, save};
+
+    #[derive(Tree, Default, Clone, PartialEq, Debug)]
+    struct Params {
+        rate: u16,
+        pid: (f32, f32, f32),
+        id: [u8; 4],
+    }
+
+    #[derive(Tree, Default)]
+    struct Large {
+        values: [[u8; 8]; 2],
+    }
+
+    const SLOTS: [core::ops::Range<u32>; 2] = [0..512, 512..1024];
+
+    fn params(rate: u16) -> Params {
+        Params {
+            rate,
+            pid: (1.0, 0.5, 0.0),
+            id: [1, 2, 3, 4],
+        }
+    }
+
+    fn loaded(flash: RamFlash<1024>) -> (Params, usize) {
+        let mut storage = SnapshotStorage::new(flash, SLOTS).unwrap();
+        let mut params = Params::default();
+        let applied = load(&mut params, &mut storage).unwrap();
+        (params, applied)
+    }
+
+    #[test]
+    fn alternating_slots() {
+        let mut storage = SnapshotStorage::new(RamFlash::<1024>::new(), SLOTS).unwrap();
+        assert_eq!(storage.sequence(), Ok(None));
+
+        for rate in 0..5 {
+            save(&params(rate), &mut storage).unwrap();
+            assert_eq!(storage.sequence(), Ok(Some(u32::from(rate))));
+        }
+
+        // Both slots hold a snapshot, the newest is loaded
+        let flash = storage.into_inner();
+        assert!(flash.as_bytes()[..4] == *b"MPSN" && flash.as_bytes()[512..516] == *b"MPSN");
+        assert_eq!(loaded(flash), (params(4), 8));
+
+        // Too many parameters for a slot
+        let mut storage = SnapshotStorage::new(RamFlash::<1024>::new(), SLOTS).unwrap();
+        save(&params(1), &mut storage).unwrap();
+        assert_eq!(save(&Large::default(), &mut storage), Err(FlashError::Full));
+        assert_eq!(storage.sequence(), Ok(Some(0)));
+    }
+
+    #[test]
+    fn invalid_slots() {
+        let new = |slots| SnapshotStorage::new(RamFlash::<1024>::new(), slots).err();
+        assert_eq!(new([0..256, 256..512]), None);
+        assert_eq!(new([0..512, 256..768]), Some(FlashError::InvalidRegion));
+        assert_eq!(new([256..512, 0..512]), Some(FlashError::InvalidRegion));
+        assert_eq!(new([0..256, 256..256]), Some(FlashError::InvalidRegion));
+        assert_eq!(new([0..128, 256..512]), Some(FlashError::InvalidRegion));
+    }
+
+    #[test]
+    fn interrupted_write() {
+        // A flash without any snapshot, and with snapshots in one or both slots
+        let mut flashes = vec![RamFlash::<1024>::new()];
+        for rate in 1..=2 {
+            let mut storage =
+                SnapshotStorage::new(flashes[flashes.len() - 1].clone(), SLOTS).unwrap();
+            save(&params(rate), &mut storage).unwrap();
+            flashes.push(storage.into_inner());
+        }
+
+        for (previous, flash) in flashes.into_iter().enumerate() {
+            let expected = match previous {
+                0 => (Params::default(), 0),
+                rate => (params(u16::try_from(rate).unwrap()), 8),
+            };
+
+            // Find the number of bytes written by a complete save
+            let mut storage = SnapshotStorage::new(flash.clone(), SLOTS).unwrap();
+            save(&params(100), &mut storage).unwrap();
+            let total = storage.into_inner().written() - flash.written();
+            assert_eq!(total, 9 * 32);
+
+            for cut in 0..total {
+                let mut interrupted = flash.clone();
+                interrupted.set_write_limit(Some(flash.written() + cut));
+
+                let mut storage = SnapshotStorage::new(interrupted, SLOTS).unwrap();
+                assert!(matches!(
+                    save(&params(100), &mut storage),
+                    Err(FlashError::Flash(_))
+                ));
+
+                // The previous snapshot is still loaded
+                let mut interrupted = storage.into_inner();
+                interrupted.set_write_limit(None);
+                assert_eq!(loaded(interrupted.clone()), expected, "cut at {cut}");
+
+                // Saving again overwrites the interrupted snapshot
+                let mut storage = SnapshotStorage::new(interrupted, SLOTS).unwrap();
+                save(&params(200), &mut storage).unwrap();
+                assert_eq!(loaded(storage.into_inner()), (params(200), 8));
+            }
+        }
+    }
+}
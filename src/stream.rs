@@ -0,0 +1,298 @@
+use heapless::Deque;
+
+use crate::iter::ParamCursor;
+use crate::server::Response;
+use crate::{Error, Parameter, Tree, iter};
+
+/// A paced stream of `PARAM_VALUE` responses, for answering list requests over slow links.
+///
+/// Unlike [`iter::ParamIter`], the stream only stores the position of the next parameter,
+/// and borrows the tree only while [`ParamStream::tick`] is producing responses. The tree may
+/// therefore be modified between ticks, and the stream can be paused and resumed at any index.
+///
+/// Up to `N` indices can be queued for re-sending, which are sent before the ongoing list.
+pub struct ParamStream<'a, const N: usize> {
+    name: Option<&'a str>,
+    // The number of parameters in the tree, as of the last refresh
+    count: u16,
+    // The maximum number of responses per tick
+    rate: usize,
+    // The index of the next parameter of the ongoing list
+    list_index: Option<u16>,
+    // The index of the next parameter yielded by the cursor, which follows the list
+    cursor: Option<(u16, ParamCursor)>,
+    // Indices to send before continuing the list
+    resend: Deque<u16, N>,
+}
+
+impl<'a, const N: usize> ParamStream<'a, N> {
+    /// Create a new stream for the given tree, sending up to `rate` parameters per tick
+    pub fn new(tree: &dyn Tree, rate: usize) -> Self {
+        Self::with_name(tree, None, rate)
+    }
+
+    /// Create a new stream for the given tree with a "root" name defined,
+    /// sending up to `rate` parameters per tick
+    pub fn new_named(tree: &dyn Tree, name: &'a str, rate: usize) -> Self {
+        Self::with_name(tree, Some(name), rate)
+    }
+
+    fn with_name(tree: &dyn Tree, name: Option<&'a str>, rate: usize) -> Self {
+        Self {
+            name,
+            count: iter::count(iter::ParamIter::new(tree, name)),
+            rate,
+            list_index: None,
+            cursor: None,
+            resend: Deque::new(),
+        }
+    }
+
+    /// The number of parameters in the stream, as of the last refresh
+    #[must_use]
+    pub fn param_count(&self) -> u16 {
+        self.count
+    }
+
+    /// The index of the next parameter of the ongoing list, if any
+    #[must_use]
+    pub fn list_index(&self) -> Option<u16> {
+        self.list_index
+    }
+
+    /// Returns `true` while there are parameters left to send
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.list_index.is_some() || !self.resend.is_empty()
+    }
+
+    /// Start sending the full list from the beginning
+    pub fn start(&mut self) {
+        self.resume_at(0);
+    }
+
+    /// Continue sending the list from the given index, e.g. after an interruption
+    pub fn resume_at(&mut self, index: u16) {
+        self.list_index = (index < self.count).then_some(index);
+    }
+
+    /// Stop sending the list, and drop any queued re-sends
+    pub fn stop(&mut self) {
+        self.list_index = None;
+        self.resend.clear();
+    }
+
+    /// Queue a single parameter to be sent again, ahead of the ongoing list.
+    ///
+    /// Indices which are already queued are only sent once.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NoSuchParameter`] if the index is outside the list,
+    /// and [`Error::CapacityExceeded`] if `N` indices are already queued.
+    pub fn resend(&mut self, index: u16) -> Result<(), Error> {
+        if index >= self.count {
+            return Err(Error::NoSuchParameter);
+        }
+
+        if self.resend.iter().any(|queued| *queued == index) {
+            return Ok(());
+        }
+
+        self.resend
+            .push_back(index)
+            .map_err(|_| Error::CapacityExceeded)
+    }
+
+    /// Count the parameters again, after the layout of the tree changed.
+    ///
+    /// Selecting the variant of an enum changes the parameters of the tree, so this should
+    /// be called after such a change. The list then ends at the new count, and re-sends of
+    /// indices beyond it are dropped.
+    pub fn refresh(&mut self, tree: &dyn Tree) {
+        let count = iter::count(iter::ParamIter::new(tree, self.name));
+        self.count = count;
+        self.cursor = None;
+        self.list_index = self.list_index.filter(|index| *index < count);
+    }
+
+    /// Produce the responses of this tick, up to the rate of the stream.
+    ///
+    /// The stream only advances for the responses which are actually taken from the
+    /// iterator, so dropping it early leaves the remaining parameters for the next tick.
+    ///
+    /// The parameters are not counted again, see [`ParamStream::refresh`]. If the tree
+    /// has fewer parameters than counted, the list ends early once they are all sent.
+    pub fn tick<'t>(&'t mut self, tree: &'t dyn Tree) -> impl Iterator<Item = Response> + 't {
+        let rate = self.rate;
+        core::iter::from_fn(move || self.next_response(tree)).take(rate)
+    }
+
+    fn next_response(&mut self, tree: &dyn Tree) -> Option<Response> {
+        let (index, param) = loop {
+            if let Some(index) = self.resend.pop_front() {
+                if let Some(param) = iter::nth(iter::ParamIter::new(tree, self.name), index) {
+                    break (index, param);
+                }
+                continue;
+            }
+
+            let index = self.list_index?;
+            let Some(param) = self.next_listed(tree, index) else {
+                // The tree has fewer parameters than counted, so the list is done
+                self.list_index = None;
+                return None;
+            };
+            self.list_index = index.checked_add(1).filter(|next| *next < self.count);
+            break (index, param);
+        };
+
+        Some(Response {
+            param,
+            param_index: index,
+            param_count: self.count,
+        })
+    }
+
+    // Advance the cursor of the list, which is only positioned from
+    // the root if the list does not continue where it left off
+    fn next_listed(&mut self, tree: &dyn Tree, index: u16) -> Option<Parameter> {
+        if self.cursor.as_ref().is_none_or(|(next, _)| *next != index) {
+            let mut cursor = ParamCursor::new(self.name);
+            for _ in 0..index {
+                iter::next_param(&mut cursor, tree)?;
+            }
+            self.cursor = Some((index, cursor));
+        }
+
+        let (next, cursor) = self.cursor.as_mut()?;
+        let param = iter::next_param(cursor, tree)?;
+        *next = index.checked_add(1)?;
+        Some(param)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParamStream;
+    use crate as mav_param;
+    use mav_param::{Error, Tree, Value};
+
+    #[derive(Tree, Default)]
+    struct Params {
+        rate: [u16; 4],
+        gain: f32,
+    }
+
+    fn indices(stream: &mut ParamStream<4>, params: &Params) -> Vec<u16> {
+        stream.tick(params).map(|r| r.param_index).collect()
+    }
+
+    #[test]
+    fn paced() {
+        let mut params = Params::default();
+        let mut stream = ParamStream::<4>::new_named(&params, "mav", 2);
+        assert_eq!(stream.param_count(), 5);
+        assert!(!stream.is_active());
+        assert_eq!(indices(&mut stream, &params), []);
+
+        stream.start();
+        assert_eq!(indices(&mut stream, &params), [0, 1]);
+
+        // The tree can be modified between ticks
+        params.rate[2] = 100;
+
+        let responses: Vec<_> = stream.tick(&params).collect();
+        assert_eq!(responses[0].param.ident.as_str(), "mav.rate.2");
+        assert_eq!(responses[0].param.value, Value::U16(100));
+        assert_eq!(responses[1].param_index, 3);
+
+        assert_eq!(indices(&mut stream, &params), [4]);
+        assert!(!stream.is_active());
+    }
+
+    #[test]
+    fn interrupted() {
+        let params = Params::default();
+        let mut stream = ParamStream::<4>::new(&params, 3);
+
+        // Only one response is taken this tick
+        stream.start();
+        assert_eq!(stream.tick(&params).next().map(|r| r.param_index), Some(0));
+        assert_eq!(stream.list_index(), Some(1));
+
+        stream.stop();
+        assert_eq!(indices(&mut stream, &params), []);
+
+        stream.resume_at(3);
+        assert_eq!(indices(&mut stream, &params), [3, 4]);
+
+        stream.resume_at(5);
+        assert!(!stream.is_active());
+    }
+
+    #[test]
+    fn resend() {
+        let params = Params::default();
+        let mut stream = ParamStream::<4>::new(&params, 3);
+
+        stream.resume_at(2);
+        assert_eq!(stream.resend(4), Ok(()));
+        assert_eq!(stream.resend(0), Ok(()));
+        assert_eq!(stream.resend(4), Ok(()));
+        assert_eq!(stream.resend(5), Err(Error::NoSuchParameter));
+
+        // Re-sends are sent ahead of the list
+        assert_eq!(indices(&mut stream, &params), [4, 0, 2]);
+        assert_eq!(indices(&mut stream, &params), [3, 4]);
+
+        for index in 0..4 {
+            stream.resend(index).unwrap();
+        }
+        assert_eq!(stream.resend(4), Err(Error::CapacityExceeded));
+    }
+
+    #[test]
+    fn variant_count() {
+        #[derive(Tree)]
+        enum Output {
+            Pwm { rate: u16 },
+            Dshot { rate: u16, telemetry: bool },
+        }
+
+        let mut output = Output::Dshot {
+            rate: 600,
+            telemetry: true,
+        };
+        let mut stream = ParamStream::<4>::new(&output, 2);
+        assert_eq!(stream.param_count(), 3);
+
+        stream.start();
+        assert_eq!(stream.tick(&output).count(), 2);
+        stream.resend(2).unwrap();
+
+        // Refreshing after selecting another variant updates the count and ends the list
+        output = Output::Pwm { rate: 400 };
+        stream.refresh(&output);
+        assert_eq!(stream.tick(&output).count(), 0);
+        assert_eq!(stream.param_count(), 2);
+        assert!(!stream.is_active());
+
+        output = Output::Dshot {
+            rate: 600,
+            telemetry: false,
+        };
+        stream.refresh(&output);
+        stream.start();
+        let responses: Vec<_> = stream.tick(&output).collect();
+        assert_eq!(responses[1].param_count, 3);
+        assert_eq!(responses[1].param.ident.as_str(), "rate");
+
+        // Without a refresh, the list ends once the cursor finds no more parameters
+        output = Output::Pwm { rate: 400 };
+        stream.start();
+        assert_eq!(stream.tick(&output).count(), 2);
+        assert_eq!(stream.tick(&output).count(), 0);
+        assert!(!stream.is_active());
+    }
+}
@@ -0,0 +1,381 @@
+use heapless::Vec;
+
+use crate::mirror::ParamMirror;
+use crate::server::{ParamServer, Request, Response};
+use crate::{Tree, param_iter_named};
+
+/// The behaviour of a simulated [`Link`].
+///
+/// Rates are given in percent of the messages sent over the link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LinkConfig {
+    /// Chance of a message being lost
+    pub drop_percent: u8,
+    /// Chance of a message being delivered twice
+    pub duplicate_percent: u8,
+    /// Chance of a message being delivered before the messages sent ahead of it
+    pub reorder_percent: u8,
+    /// Seed of the pseudo-random generator, the same seed gives the same behaviour
+    pub seed: u64,
+}
+
+/// A lossy in-memory link carrying messages in one direction.
+///
+/// Up to `N` messages can be in transit, any messages sent beyond that are dropped.
+pub struct Link<T, const N: usize> {
+    queue: Vec<T, N>,
+    config: LinkConfig,
+    rng: Rng,
+}
+
+impl<T: Clone, const N: usize> Link<T, N> {
+    /// Create a new empty link
+    #[must_use]
+    pub fn new(config: LinkConfig) -> Self {
+        Self {
+            queue: Vec::new(),
+            config,
+            rng: Rng(config.seed),
+        }
+    }
+
+    /// Send a message over the link, which may drop or duplicate it
+    pub fn send(&mut self, message: T) {
+        if self.rng.chance(self.config.drop_percent) {
+            return;
+        }
+
+        if self.rng.chance(self.config.duplicate_percent) {
+            let _ = self.queue.push(message.clone());
+        }
+
+        let _ = self.queue.push(message);
+    }
+
+    /// Receive the next message from the link, which may not be the oldest one
+    pub fn recv(&mut self) -> Option<T> {
+        if self.queue.is_empty() {
+            return None;
+        }
+
+        let index = if self.rng.chance(self.config.reorder_percent) {
+            self.rng.below(self.queue.len())
+        } else {
+            0
+        };
+
+        Some(self.queue.remove(index))
+    }
+
+    /// Returns `true` if no messages are in transit
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// The outcome of [`sync_params`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    /// The client received every parameter of the list
+    pub complete: bool,
+    /// The client holds exactly the parameters of [`param_iter_named`]
+    pub matches: bool,
+    /// The number of ticks it took, see [`sync_params`]
+    pub ticks: usize,
+}
+
+/// The vehicle end of [`sync`], which answers the requests of a [`Client`].
+pub trait Responder<Req, Resp> {
+    /// Handle a received request, returning the response to send if there is one
+    fn handle(&mut self, request: &Req) -> Option<Resp>;
+
+    /// Get the next response to send without a request, like the next listed parameter
+    fn poll(&mut self) -> Option<Resp>;
+
+    /// Returns `true` while [`Responder::poll`] has responses left to send
+    fn is_busy(&self) -> bool;
+}
+
+/// The ground station end of [`sync`], which requests and receives parameters.
+pub trait Client<Req, Resp> {
+    /// Receive a response sent by the [`Responder`]
+    fn receive(&mut self, response: &Resp);
+
+    /// Returns `true` once the client received every parameter
+    fn is_complete(&self) -> bool;
+
+    /// Send the requests for the parameters which are still missing
+    fn request(&mut self, send: &mut dyn FnMut(Req));
+}
+
+impl Responder<Request, Response> for ParamServer<'_> {
+    fn handle(&mut self, request: &Request) -> Option<Response> {
+        ParamServer::handle(self, request).ok().flatten()
+    }
+
+    fn poll(&mut self) -> Option<Response> {
+        ParamServer::poll(self)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.is_listing()
+    }
+}
+
+impl<const N: usize> Client<Request, Response> for ParamMirror<N> {
+    fn receive(&mut self, response: &Response) {
+        let _ = ParamMirror::receive(self, response);
+    }
+
+    fn is_complete(&self) -> bool {
+        ParamMirror::is_complete(self)
+    }
+
+    fn request(&mut self, send: &mut dyn FnMut(Request)) {
+        self.requests().for_each(send);
+    }
+}
+
+/// Synchronize the parameters of a [`Responder`] to a [`Client`] over a pair of simulated links.
+///
+/// Every tick, the client requests its missing parameters if both links are idle and the
+/// responder is not busy, the responder handles all received requests and polls one response,
+/// and the client receives all sent responses. This continues until the client is complete,
+/// or `max_ticks` have passed. Up to `N` messages can be in transit on each link.
+///
+/// The uplink and downlink use the same configuration, though with different seeds.
+/// Returns the number of ticks it took.
+pub fn sync<Req: Clone, Resp: Clone, const N: usize>(
+    responder: &mut dyn Responder<Req, Resp>,
+    client: &mut dyn Client<Req, Resp>,
+    config: LinkConfig,
+    max_ticks: usize,
+) -> usize {
+    let mut uplink = Link::<Req, N>::new(config);
+    let mut downlink = Link::<Resp, N>::new(LinkConfig {
+        seed: !config.seed,
+        ..config
+    });
+    let mut ticks = 0;
+
+    while ticks < max_ticks {
+        if uplink.is_empty() && downlink.is_empty() && !responder.is_busy() {
+            if client.is_complete() {
+                break;
+            }
+
+            client.request(&mut |request| uplink.send(request));
+        }
+
+        while let Some(request) = uplink.recv() {
+            if let Some(response) = responder.handle(&request) {
+                downlink.send(response);
+            }
+        }
+
+        if let Some(response) = responder.poll() {
+            downlink.send(response);
+        }
+
+        while let Some(response) = downlink.recv() {
+            client.receive(&response);
+        }
+
+        ticks += 1;
+    }
+
+    ticks
+}
+
+/// Synchronize the parameters of a tree to a client over a pair of simulated links.
+///
+/// This uses [`sync`] with a [`ParamServer`] responding to the requests of a [`ParamMirror`],
+/// which holds up to `N` parameters.
+pub fn sync_params<const N: usize>(
+    tree: &mut dyn Tree,
+    name: &str,
+    config: LinkConfig,
+    max_ticks: usize,
+) -> Report {
+    let mut mirror = ParamMirror::<N>::new();
+    let mut server = ParamServer::new_named(tree, name);
+    let ticks = sync::<_, _, N>(&mut server, &mut mirror, config, max_ticks);
+
+    let matches = mirror
+        .params()
+        .is_some_and(|params| params.cloned().eq(param_iter_named(tree, name).flatten()));
+
+    Report {
+        complete: mirror.is_complete(),
+        matches,
+        ticks,
+    }
+}
+
+// SplitMix64, which is good enough for simulating a link
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn chance(&mut self, percent: u8) -> bool {
+        percent > 0 && self.next() % 100 < u64::from(percent)
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        let bound = u64::try_from(bound).unwrap_or(u64::MAX);
+        usize::try_from(self.next() % bound).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Client, Link, LinkConfig, Report, sync, sync_params};
+    use crate as mav_param;
+    use mav_param::Tree;
+    use mav_param::mirror::ParamMirror;
+    use mav_param::server::{ParamServer, Request, Response};
+
+    #[derive(Tree, Default)]
+    struct Params {
+        id: (u8, u8),
+        rate: [u16; 8],
+        pid: [Pid; 3],
+    }
+
+    #[derive(Tree, Default)]
+    struct Pid {
+        p: f32,
+        i: f32,
+        d: f32,
+    }
+
+    fn params() -> Params {
+        let mut params = Params {
+            id: (1, 2),
+            ..Default::default()
+        };
+        params.rate[3] = 400;
+        params.pid[1].i = 0.25;
+        params
+    }
+
+    #[test]
+    fn link() {
+        let config = LinkConfig {
+            drop_percent: 50,
+            ..Default::default()
+        };
+        let mut link = Link::<u32, 64>::new(config);
+        (0..64).for_each(|message| link.send(message));
+        let received: Vec<_> = core::iter::from_fn(|| link.recv()).collect();
+
+        assert!(received.len() > 16 && received.len() < 48);
+        assert!(received.is_sorted());
+
+        // A full link drops the messages
+        let mut link = Link::<u32, 4>::new(LinkConfig::default());
+        (0..8).for_each(|message| link.send(message));
+        assert_eq!(
+            core::iter::from_fn(|| link.recv()).collect::<Vec<_>>(),
+            [0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn lossless() {
+        let mut params = params();
+        let report = sync_params::<64>(&mut params, "mav", LinkConfig::default(), 100);
+
+        // One tick per parameter, and one for the end of the list
+        assert_eq!(
+            report,
+            Report {
+                complete: true,
+                matches: true,
+                ticks: 20,
+            }
+        );
+    }
+
+    #[test]
+    fn lossy() {
+        for seed in 0..16 {
+            let config = LinkConfig {
+                drop_percent: 30,
+                duplicate_percent: 20,
+                reorder_percent: 50,
+                seed,
+            };
+
+            let (mut first, mut second) = (params(), params());
+            let report = sync_params::<64>(&mut first, "mav", config, 1000);
+            assert!(report.complete && report.matches, "seed {seed}: {report:?}");
+
+            // The same seed behaves the same
+            assert_eq!(sync_params::<64>(&mut second, "mav", config, 1000), report);
+        }
+    }
+
+    #[test]
+    fn incomplete() {
+        let config = LinkConfig {
+            drop_percent: 100,
+            ..Default::default()
+        };
+
+        let mut params = params();
+        let report = sync_params::<64>(&mut params, "mav", config, 10);
+        assert_eq!(
+            report,
+            Report {
+                complete: false,
+                matches: false,
+                ticks: 10,
+            }
+        );
+    }
+
+    #[test]
+    fn custom_client() {
+        // A client counting the responses it receives
+        struct Counting {
+            mirror: ParamMirror<64>,
+            received: usize,
+        }
+
+        impl Client<Request, Response> for Counting {
+            fn receive(&mut self, response: &Response) {
+                self.received += 1;
+                self.mirror.receive(response).unwrap();
+            }
+
+            fn is_complete(&self) -> bool {
+                self.mirror.is_complete()
+            }
+
+            fn request(&mut self, send: &mut dyn FnMut(Request)) {
+                self.mirror.requests().for_each(send);
+            }
+        }
+
+        let mut params = params();
+        let mut server = ParamServer::new_named(&mut params, "mav");
+        let mut client = Counting {
+            mirror: ParamMirror::new(),
+            received: 0,
+        };
+
+        let ticks = sync::<_, _, 64>(&mut server, &mut client, LinkConfig::default(), 100);
+        assert_eq!(ticks, 20);
+        assert_eq!(client.received, 19);
+        assert!(client.is_complete());
+    }
+}
@@ -0,0 +1,531 @@
+use heapless::Vec;
+
+use crate::iter::{self, ParamCursor};
+use crate::{
+    Error, Ident, NodeMut, NodeRef, Parameter, Tree, Value, ValueMut, get_value_mut, param_iter,
+};
+
+/// The reason a [`Transaction`] could not be committed.
+#[derive(Debug, PartialEq)]
+pub struct TransactionError {
+    /// The identifier of the first parameter which was rejected
+    pub ident: Ident,
+    /// Why the parameter was rejected
+    pub error: Error,
+}
+
+/// A set of parameter changes which are applied to a tree as a unit.
+///
+/// Parameters are staged into a bounded store of up to `N` entries. When committing,
+/// either every staged parameter is written or none of them are. Selecting the variant
+/// of an enum resets its fields, so up to `S` values of such enums are kept to restore
+/// them if the commit fails.
+///
+/// Note: Identifiers are resolved like [`crate::get_value`], so they must not include the
+/// "root" name.
+pub struct Transaction<const N: usize, const S: usize = 16> {
+    pending: Vec<Parameter, N>,
+}
+
+impl<const N: usize, const S: usize> Default for Transaction<N, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const S: usize> Transaction<N, S> {
+    /// Creates a new empty transaction.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+        }
+    }
+
+    /// Stage a parameter to be written when committing.
+    ///
+    /// If a parameter with the same identifier is already staged, its value is replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns the parameter back if the transaction is full.
+    pub fn stage(&mut self, param: Parameter) -> Result<(), Parameter> {
+        match self.pending.iter_mut().find(|p| p.ident == param.ident) {
+            Some(pending) => {
+                pending.value = param.value;
+                Ok(())
+            }
+            None => self.pending.push(param),
+        }
+    }
+
+    /// The parameters currently staged.
+    #[must_use]
+    pub fn pending(&self) -> &[Parameter] {
+        &self.pending
+    }
+
+    /// Returns `true` if no parameters are staged.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Check that the staged parameters can be committed, without changing the tree.
+    ///
+    /// The parameters are written like [`Transaction::commit`] does, and then restored, so
+    /// the fields of an enum variant selected by this transaction are checked against that
+    /// variant, and values rejected by a [`crate::Validated`] parameter are found as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first parameter which would be rejected by [`Transaction::commit`].
+    pub fn validate(&self, tree: &mut dyn Tree) -> Result<(), TransactionError> {
+        let mut written = Vec::new();
+        let mut snapshot = Vec::new();
+        self.apply_all(tree, &mut written, &mut snapshot)?;
+
+        self.restore(tree, &written, &mut snapshot);
+        Ok(())
+    }
+
+    /// Validate and apply all staged parameters to the tree, leaving the transaction empty.
+    ///
+    /// Parameters are applied from the root outwards, and the tags of enums before the
+    /// fields next to them, so the fields of a variant selected by the same transaction
+    /// are validated against that variant.
+    ///
+    /// Returns the number of parameters written.
+    ///
+    /// # Errors
+    ///
+    /// If any staged parameter is unknown, has a mismatching type or is rejected by a
+    /// [`crate::Validated`] parameter, the parameters written before it are restored in
+    /// reverse order, including all fields of the enums whose variant was changed. The
+    /// staged parameters are kept, so they can be inspected before [`Transaction::discard`].
+    /// Changing the variant of an enum with more than `S` values fails with
+    /// [`Error::CapacityExceeded`].
+    ///
+    /// Note: Use [`crate::server::ParamServer::commit`] to notify the change hook of a server.
+    pub fn commit(&mut self, tree: &mut dyn Tree) -> Result<usize, TransactionError> {
+        let mut written = Vec::new();
+        let mut snapshot = Vec::new();
+        self.apply_all(tree, &mut written, &mut snapshot)?;
+
+        self.pending.clear();
+        Ok(written.len())
+    }
+
+    // Write every staged parameter, or restore the tree if one is rejected. Every write
+    // records the staged parameter, its previous value, and the number of values of the
+    // enum taken before its variant was changed by that write.
+    fn apply_all(
+        &self,
+        tree: &mut dyn Tree,
+        written: &mut Vec<(usize, Value, usize), N>,
+        snapshot: &mut Vec<Value, S>,
+    ) -> Result<(), TransactionError> {
+        let depth = |index: &usize| self.pending[*index].ident.as_str().split('.').count();
+
+        // The capacity is the same as for the staged parameters, which keep their order per level
+        let mut order: Vec<usize, N> = (0..self.pending.len()).collect();
+        order.sort_unstable_by_key(|index| (depth(index), *index));
+
+        // Each level is written after the ones closer to the root, since writing a tag
+        // there may create its parameters. Within a level, the tags are written first.
+        for level in order.chunk_by(|a, b| depth(a) == depth(b)) {
+            let tags: Vec<bool, N> = level
+                .iter()
+                .map(|index| {
+                    let path = self.pending[*index].ident.as_str();
+                    matches!(
+                        get_value_mut(tree, path),
+                        Some(ValueMut::Validated(tag)) if tag.selects_variant()
+                    )
+                })
+                .collect();
+
+            for pass in [true, false] {
+                for (index, _) in level.iter().zip(&tags).filter(|(_, tag)| **tag == pass) {
+                    let param = &self.pending[*index];
+                    match apply(tree, param, snapshot) {
+                        // The capacity is the same as for the staged parameters
+                        Ok((previous, taken)) => {
+                            let _ = written.push((*index, previous, taken));
+                        }
+                        Err(error) => {
+                            self.restore(tree, written, snapshot);
+                            return Err(TransactionError {
+                                ident: param.ident.clone(),
+                                error,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Undo the writes in reverse order
+    fn restore(
+        &self,
+        tree: &mut dyn Tree,
+        written: &[(usize, Value, usize)],
+        snapshot: &mut Vec<Value, S>,
+    ) {
+        for (index, previous, taken) in written.iter().rev() {
+            let path = self.pending[*index].ident.as_str();
+            if let Some(mut value_mut) = get_value_mut(tree, path) {
+                value_mut.try_assign(*previous);
+            }
+
+            // The variant is restored with default fields, which are then restored in order
+            let start = snapshot.len() - taken;
+            if *taken > 0
+                && let Some(parent) = parent_mut(tree, path)
+            {
+                let mut cursor = ParamCursor::new(None);
+                for value in &snapshot[start..] {
+                    let Some(param) = iter::next_param(&mut cursor, parent) else {
+                        break;
+                    };
+                    if let Some(mut value_mut) = get_value_mut(parent, param.ident.as_str()) {
+                        value_mut.try_assign(*value);
+                    }
+                }
+            }
+            snapshot.truncate(start);
+        }
+    }
+
+    /// Drop all staged parameters without writing them.
+    pub fn discard(&mut self) {
+        self.pending.clear();
+    }
+}
+
+// Write the parameter, returning the previous value and the number of values taken
+// of the enum whose variant it changes
+fn apply<const S: usize>(
+    tree: &mut dyn Tree,
+    param: &Parameter,
+    snapshot: &mut Vec<Value, S>,
+) -> Result<(Value, usize), Error> {
+    let path = param.ident.as_str();
+    let value_mut = get_value_mut(tree, path).ok_or(Error::NoSuchParameter)?;
+    let previous = value_mut.owned();
+
+    let (expected, received) = (previous.param_type(), param.value.param_type());
+    if expected != received {
+        return Err(Error::TypeMismatch { expected, received });
+    }
+
+    let reshapes = matches!(&value_mut, ValueMut::Validated(tag) if tag.selects_variant())
+        && !previous.bitwise_eq(param.value);
+
+    let start = snapshot.len();
+    if reshapes {
+        let parent = parent_ref(tree, path).ok_or(Error::NoSuchParameter)?;
+        for param in param_iter(parent).flatten() {
+            if snapshot.push(param.value).is_err() {
+                snapshot.truncate(start);
+                return Err(Error::CapacityExceeded);
+            }
+        }
+    }
+
+    let result = get_value_mut(tree, path)
+        .ok_or(Error::NoSuchParameter)
+        .and_then(|mut value_mut| value_mut.assign(param.value));
+
+    match result {
+        Ok(()) => Ok((previous, snapshot.len() - start)),
+        Err(error) => {
+            snapshot.truncate(start);
+            Err(error)
+        }
+    }
+}
+
+// The tree holding the parameter at the given path
+fn parent_ref<'a>(mut tree: &'a dyn Tree, path: &str) -> Option<&'a dyn Tree> {
+    let Some((parent, _)) = path.trim_start_matches('.').rsplit_once('.') else {
+        return Some(tree);
+    };
+    for segment in parent.split('.') {
+        match tree.get_ref(segment)? {
+            NodeRef::Tree(node_ref) => tree = node_ref,
+            NodeRef::Value(_) => return None,
+        }
+    }
+    Some(tree)
+}
+
+// The tree holding the parameter at the given path, mutably
+fn parent_mut<'a>(mut tree: &'a mut dyn Tree, path: &str) -> Option<&'a mut dyn Tree> {
+    let Some((parent, _)) = path.trim_start_matches('.').rsplit_once('.') else {
+        return Some(tree);
+    };
+    for segment in parent.split('.') {
+        match tree.get_mut(segment)? {
+            NodeMut::Tree(node_mut) => tree = node_mut,
+            NodeMut::Value(_) => return None,
+        }
+    }
+    Some(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Transaction, TransactionError};
+    use crate as mav_param;
+    use mav_param::{Error, Ident, ParamType, Parameter, Tree, Value};
+
+    #[derive(Tree, Default)]
+    struct Params {
+        pid: Pid,
+        rate: u16,
+    }
+
+    #[derive(Tree, Default)]
+    struct Pid {
+        p: f32,
+        i: f32,
+        d: f32,
+    }
+
+    fn param(ident: &str, value: Value) -> Parameter {
+        Parameter {
+            ident: Ident::from_str_truncated(ident),
+            value,
+        }
+    }
+
+    #[test]
+    fn commit() {
+        let mut params = Params::default();
+        let mut transaction = Transaction::<4>::new();
+
+        assert_eq!(transaction.stage(param("pid.p", Value::F32(1.0))), Ok(()));
+        assert_eq!(transaction.stage(param("pid.i", Value::F32(0.5))), Ok(()));
+        assert_eq!(transaction.stage(param("pid.p", Value::F32(2.0))), Ok(()));
+        assert_eq!(transaction.pending().len(), 2);
+
+        // Nothing is written until the commit
+        assert!(params.pid.p.abs() < f32::EPSILON);
+
+        assert_eq!(transaction.commit(&mut params), Ok(2));
+        assert!(transaction.is_empty());
+        assert!((params.pid.p - 2.0).abs() < f32::EPSILON);
+        assert!((params.pid.i - 0.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn rejected_commit_writes_nothing() {
+        let mut params = Params::default();
+        let mut transaction = Transaction::<4>::new();
+
+        transaction.stage(param("pid.p", Value::F32(1.0))).unwrap();
+        transaction.stage(param("rate", Value::U32(100))).unwrap();
+        transaction.stage(param("pid.d", Value::F32(0.1))).unwrap();
+
+        assert_eq!(
+            transaction.commit(&mut params),
+            Err(TransactionError {
+                ident: Ident::from_str_truncated("rate"),
+                error: Error::TypeMismatch {
+                    expected: ParamType::Uint16,
+                    received: ParamType::Uint32,
+                },
+            })
+        );
+        assert!(params.pid.p.abs() < f32::EPSILON);
+        assert_eq!(transaction.pending().len(), 3);
+
+        transaction.discard();
+        transaction.stage(param("pid.x", Value::F32(1.0))).unwrap();
+        assert_eq!(
+            transaction.validate(&mut params),
+            Err(TransactionError {
+                ident: Ident::from_str_truncated("pid.x"),
+                error: Error::NoSuchParameter,
+            })
+        );
+    }
+
+    #[test]
+    fn bounded() {
+        let mut transaction = Transaction::<1>::new();
+
+        assert_eq!(transaction.stage(param("pid.p", Value::F32(1.0))), Ok(()));
+        assert_eq!(
+            transaction.stage(param("pid.i", Value::F32(1.0))),
+            Err(param("pid.i", Value::F32(1.0)))
+        );
+
+        // Replacing a staged value does not need more space
+        assert_eq!(transaction.stage(param("pid.p", Value::F32(3.0))), Ok(()));
+        assert_eq!(transaction.pending(), [param("pid.p", Value::F32(3.0))]);
+    }
+
+    #[test]
+    fn validated_rollback() {
+        #[derive(mav_param::Node, Default, Debug, PartialEq)]
+        #[repr(u8)]
+        enum Mode {
+            #[default]
+            Manual = 0,
+            Auto = 1,
+        }
+
+        #[derive(Tree, Default)]
+        struct Params {
+            rate: u16,
+            mode: Mode,
+            gain: f32,
+        }
+
+        let mut params = Params::default();
+        let mut transaction = Transaction::<4>::new();
+        transaction.stage(param("rate", Value::U16(50))).unwrap();
+        transaction.stage(param("mode", Value::U8(2))).unwrap();
+
+        // The type is valid, but the value is rejected when written
+        assert_eq!(
+            transaction.validate(&mut params),
+            Err(TransactionError {
+                ident: Ident::from_str_truncated("mode"),
+                error: Error::InvalidValue,
+            })
+        );
+        assert_eq!(
+            transaction.commit(&mut params),
+            Err(TransactionError {
+                ident: Ident::from_str_truncated("mode"),
+                error: Error::InvalidValue,
+            })
+        );
+        assert_eq!(params.rate, 0);
+
+        transaction.stage(param("mode", Value::U8(1))).unwrap();
+        assert_eq!(transaction.commit(&mut params), Ok(2));
+        assert_eq!((params.rate, params.mode), (50, Mode::Auto));
+    }
+
+    #[derive(Tree, Default, Debug, PartialEq)]
+    struct Ekf {
+        q: f32,
+        r: f32,
+    }
+
+    #[derive(Tree, Debug, PartialEq)]
+    enum Estimator {
+        Ekf(Ekf),
+        Complementary { gain: f32, bias: bool },
+    }
+
+    #[derive(Tree)]
+    struct Nav {
+        rate: u16,
+        est: Estimator,
+    }
+
+    #[test]
+    fn variant_switch() {
+        let mut nav = Nav {
+            rate: 10,
+            est: Estimator::Complementary {
+                gain: 0.5,
+                bias: true,
+            },
+        };
+
+        // The fields of the selected variant don't exist yet, but are staged first
+        let mut transaction = Transaction::<4>::new();
+        transaction
+            .stage(param("est.ekf.r", Value::F32(2.0)))
+            .unwrap();
+        transaction.stage(param("est.tag", Value::U8(0))).unwrap();
+
+        // Validating selects the variant like the commit does, and restores it afterwards
+        assert_eq!(transaction.validate(&mut nav), Ok(()));
+        assert_eq!(
+            nav.est,
+            Estimator::Complementary {
+                gain: 0.5,
+                bias: true
+            }
+        );
+        transaction
+            .stage(param("est.gain", Value::F32(1.0)))
+            .unwrap();
+        assert_eq!(
+            transaction.validate(&mut nav),
+            Err(TransactionError {
+                ident: Ident::from_str_truncated("est.gain"),
+                error: Error::NoSuchParameter,
+            })
+        );
+
+        transaction.discard();
+        transaction
+            .stage(param("est.ekf.r", Value::F32(2.0)))
+            .unwrap();
+        transaction.stage(param("est.tag", Value::U8(0))).unwrap();
+        assert_eq!(transaction.commit(&mut nav), Ok(2));
+        assert_eq!(nav.est, Estimator::Ekf(Ekf { q: 0.0, r: 2.0 }));
+    }
+
+    #[test]
+    fn variant_rollback() {
+        let mut nav = Nav {
+            rate: 10,
+            est: Estimator::Complementary {
+                gain: 0.5,
+                bias: true,
+            },
+        };
+
+        // The variant is changed and one of its fields written, then the last write fails
+        let mut transaction = Transaction::<4>::new();
+        transaction.stage(param("rate", Value::U16(20))).unwrap();
+        transaction.stage(param("est.tag", Value::U8(0))).unwrap();
+        transaction
+            .stage(param("est.ekf.q", Value::F32(1.0)))
+            .unwrap();
+        transaction
+            .stage(param("est.ekf.p", Value::F32(1.0)))
+            .unwrap();
+        assert_eq!(
+            transaction.commit(&mut nav),
+            Err(TransactionError {
+                ident: Ident::from_str_truncated("est.ekf.p"),
+                error: Error::NoSuchParameter,
+            })
+        );
+
+        // The whole enum is restored, including the fields of the previous variant
+        assert_eq!(nav.rate, 10);
+        assert_eq!(
+            nav.est,
+            Estimator::Complementary {
+                gain: 0.5,
+                bias: true
+            }
+        );
+
+        // Enums with more values than kept cannot change their variant
+        let mut transaction = Transaction::<4, 2>::new();
+        transaction.stage(param("est.tag", Value::U8(0))).unwrap();
+        assert_eq!(
+            transaction.commit(&mut nav),
+            Err(TransactionError {
+                ident: Ident::from_str_truncated("est.tag"),
+                error: Error::CapacityExceeded,
+            })
+        );
+        assert!(matches!(nav.est, Estimator::Complementary { .. }));
+    }
+}
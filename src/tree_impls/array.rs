@@ -15,6 +15,14 @@ impl<T: Node> Tree for [T; 1] {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get(index).map(Node::node_ref)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.as_mut_slice().get_mut(index).map(Node::node_mut)
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0"]
     }
@@ -37,6 +45,14 @@ impl<T: Node> Tree for [T; 2] {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get(index).map(Node::node_ref)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.as_mut_slice().get_mut(index).map(Node::node_mut)
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1"]
     }
@@ -61,6 +77,14 @@ impl<T: Node> Tree for [T; 3] {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get(index).map(Node::node_ref)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.as_mut_slice().get_mut(index).map(Node::node_mut)
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2"]
     }
@@ -87,6 +111,14 @@ impl<T: Node> Tree for [T; 4] {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get(index).map(Node::node_ref)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.as_mut_slice().get_mut(index).map(Node::node_mut)
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3"]
     }
@@ -115,6 +147,14 @@ impl<T: Node> Tree for [T; 5] {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get(index).map(Node::node_ref)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.as_mut_slice().get_mut(index).map(Node::node_mut)
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4"]
     }
@@ -145,6 +185,14 @@ impl<T: Node> Tree for [T; 6] {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get(index).map(Node::node_ref)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.as_mut_slice().get_mut(index).map(Node::node_mut)
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4", "5"]
     }
@@ -177,6 +225,14 @@ impl<T: Node> Tree for [T; 7] {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get(index).map(Node::node_ref)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.as_mut_slice().get_mut(index).map(Node::node_mut)
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4", "5", "6"]
     }
@@ -211,6 +267,14 @@ impl<T: Node> Tree for [T; 8] {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get(index).map(Node::node_ref)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.as_mut_slice().get_mut(index).map(Node::node_mut)
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4", "5", "6", "7"]
     }
@@ -247,6 +311,14 @@ impl<T: Node> Tree for [T; 9] {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get(index).map(Node::node_ref)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.as_mut_slice().get_mut(index).map(Node::node_mut)
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4", "5", "6", "7", "8"]
     }
@@ -285,6 +357,14 @@ impl<T: Node> Tree for [T; 10] {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.get(index).map(Node::node_ref)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.as_mut_slice().get_mut(index).map(Node::node_mut)
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]
     }
@@ -1,2 +1,4 @@
 pub mod array;
+pub mod option;
 pub mod tuple;
+pub mod variants;
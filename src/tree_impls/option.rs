@@ -0,0 +1,447 @@
+use crate::{Error, Node, NodeMut, NodeRef, ParamType, Tree, Validated, Value, ValueMut};
+
+/// An optional value, which keeps its value while disabled.
+///
+/// Unlike an [`Option`], the value is always present, so it is listed as a tree of
+/// the presence flag `en` and the value `val`, whether enabled or not. The parameter
+/// count therefore does not depend on the flag, the value can be written in both
+/// states, and disabling and enabling it again restores the last value. The value
+/// may be a primitive as well as another tree.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Optional<T> {
+    pub enabled: bool,
+    pub value: T,
+}
+
+impl<T> Optional<T> {
+    /// Create an enabled value
+    pub const fn some(value: T) -> Self {
+        Self {
+            enabled: true,
+            value,
+        }
+    }
+
+    /// Create a disabled value, which is kept until enabled
+    pub const fn none(value: T) -> Self {
+        Self {
+            enabled: false,
+            value,
+        }
+    }
+
+    /// Get the value, if enabled
+    pub fn get(&self) -> Option<&T> {
+        self.enabled.then_some(&self.value)
+    }
+
+    /// Get the value mutably, if enabled
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.enabled.then_some(&mut self.value)
+    }
+}
+
+impl<T: Clone> Optional<T> {
+    /// Get a copy of the value as an [`Option`]
+    pub fn to_option(&self) -> Option<T> {
+        self.get().cloned()
+    }
+}
+
+impl<T: Default> From<Option<T>> for Optional<T> {
+    fn from(option: Option<T>) -> Self {
+        match option {
+            Some(value) => Self::some(value),
+            None => Self::none(T::default()),
+        }
+    }
+}
+
+impl<T> From<Optional<T>> for Option<T> {
+    fn from(optional: Optional<T>) -> Self {
+        optional.enabled.then_some(optional.value)
+    }
+}
+
+impl<T: Node> Tree for Optional<T> {
+    fn get_ref<'a>(&'a self, path: &str) -> Option<NodeRef<'a>> {
+        match path {
+            "en" => self.get_ref_at(0),
+            "val" => self.get_ref_at(1),
+            _ => None,
+        }
+    }
+
+    fn get_mut<'a>(&'a mut self, path: &str) -> Option<NodeMut<'a>> {
+        match path {
+            "en" => self.get_mut_at(0),
+            "val" => self.get_mut_at(1),
+            _ => None,
+        }
+    }
+
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.enabled.node_ref()),
+            1 => Some(self.value.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.enabled.node_mut()),
+            1 => Some(self.value.node_mut()),
+            _ => None,
+        }
+    }
+
+    fn entries(&self) -> &'static [&'static str] {
+        &["en", "val"]
+    }
+}
+
+// An `Option` is listed as a tree of the presence flag `en` and the value `val`.
+//
+// A `None` holds no value, so a leaf reads as its default while absent, and writing it
+// makes the value present. A tree can't be listed without a value, so it is only listed
+// while present, and the parameter count then changes with the flag.
+impl<T: Node + Default> Tree for Option<T> {
+    fn get_ref<'a>(&'a self, path: &str) -> Option<NodeRef<'a>> {
+        match path {
+            "en" => self.get_ref_at(0),
+            "val" => self.get_ref_at(1),
+            _ => None,
+        }
+    }
+
+    fn get_mut<'a>(&'a mut self, path: &str) -> Option<NodeMut<'a>> {
+        match path {
+            "en" => self.get_mut_at(0),
+            "val" => self.get_mut_at(1),
+            _ => None,
+        }
+    }
+
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match (index, self) {
+            (0, _) => Some(NodeRef::Value(Validated::value(self))),
+            (1, Some(value)) => Some(value.node_ref()),
+            (1, None) => default_value::<T>().map(NodeRef::Value),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(NodeMut::Value(ValueMut::Validated(self))),
+            1 if self.is_some() => self.as_mut().map(Node::node_mut),
+            1 if default_value::<T>().is_some() => {
+                // SAFETY: `Absent` is a transparent wrapper of the option, so it has the same
+                // layout, and the reference keeps the lifetime of the borrow of `self`
+                let absent = unsafe { &mut *core::ptr::from_mut(self).cast::<Absent<T>>() };
+                Some(NodeMut::Value(ValueMut::Validated(absent)))
+            }
+            _ => None,
+        }
+    }
+
+    fn entries(&self) -> &'static [&'static str] {
+        if self.is_none() && default_value::<T>().is_none() {
+            &["en"]
+        } else {
+            &["en", "val"]
+        }
+    }
+}
+
+// The presence flag of an option, which sets the value to its default when enabled
+impl<T: Node + Default> Validated for Option<T> {
+    fn value(&self) -> Value {
+        Value::U8(u8::from(self.is_some()))
+    }
+
+    fn assign(&mut self, value: Value) -> Result<(), Error> {
+        match value {
+            Value::U8(0) => *self = None,
+            Value::U8(1) => _ = self.get_or_insert_with(T::default),
+            Value::U8(_) => return Err(Error::InvalidValue),
+            _ => {
+                return Err(Error::TypeMismatch {
+                    expected: ParamType::Uint8,
+                    received: value.param_type(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn selects_variant(&self) -> bool {
+        // Like the tag of an enum, the flag drops or resets the value next to it
+        true
+    }
+}
+
+// The value of an absent leaf, which is only made present once a value is assigned
+#[repr(transparent)]
+struct Absent<T>(Option<T>);
+
+impl<T: Node + Default> Validated for Absent<T> {
+    fn value(&self) -> Value {
+        match &self.0 {
+            Some(value) => leaf_value(value),
+            None => default_value::<T>(),
+        }
+        .unwrap_or(Value::U8(0))
+    }
+
+    fn assign(&mut self, value: Value) -> Result<(), Error> {
+        let mut present = T::default();
+        match present.node_mut() {
+            NodeMut::Value(mut value_mut) => value_mut.assign(value)?,
+            NodeMut::Tree(_) => return Err(Error::NoSuchParameter),
+        }
+        self.0 = Some(present);
+        Ok(())
+    }
+}
+
+// The value of a leaf, or `None` for a tree
+fn leaf_value<T: Node>(node: &T) -> Option<Value> {
+    match node.node_ref() {
+        NodeRef::Value(value) => Some(value),
+        NodeRef::Tree(_) => None,
+    }
+}
+
+// The default value of a leaf, which is listed in place of an absent value
+fn default_value<T: Node + Default>() -> Option<Value> {
+    leaf_value(&T::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Optional;
+    use crate as mav_param;
+    use mav_param::{Error, Node, Tree, Value, get_value, get_value_mut, param_count, param_iter};
+
+    #[derive(Node, Default, Debug, PartialEq, Clone, Copy)]
+    #[repr(u8)]
+    enum Source {
+        #[default]
+        Gps = 1,
+        Vision = 2,
+    }
+
+    #[derive(Tree, Default, Debug, PartialEq, Clone, Copy)]
+    struct Filter {
+        cutoff: f32,
+        order: u8,
+    }
+
+    #[derive(Tree, Default)]
+    struct Params {
+        rate: u16,
+        limit: Optional<f32>,
+        source: Optional<Source>,
+        lpf: Optional<Filter>,
+    }
+
+    #[derive(Tree, Default)]
+    struct Native {
+        limit: Option<f32>,
+        source: Option<Source>,
+        lpf: Option<Filter>,
+    }
+
+    fn assign(params: &mut dyn Tree, ident: &str, value: Value) -> Result<(), Error> {
+        get_value_mut(params, ident)
+            .ok_or(Error::NoSuchParameter)?
+            .assign(value)
+    }
+
+    #[test]
+    fn optional() {
+        let mut params = Params {
+            rate: 50,
+            limit: Optional::some(2.5),
+            ..Default::default()
+        };
+
+        // The values are listed whether enabled or not
+        let listed: Vec<_> = param_iter(&params)
+            .flatten()
+            .map(|p| (p.ident.as_str().to_owned(), p.value))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("rate".to_owned(), Value::U16(50)),
+                ("limit.en".to_owned(), Value::U8(1)),
+                ("limit.val".to_owned(), Value::F32(2.5)),
+                ("source.en".to_owned(), Value::U8(0)),
+                ("source.val".to_owned(), Value::U8(1)),
+                ("lpf.en".to_owned(), Value::U8(0)),
+                ("lpf.val.cutoff".to_owned(), Value::F32(0.0)),
+                ("lpf.val.order".to_owned(), Value::U8(0)),
+            ]
+        );
+        assert_eq!(param_count(&params), 8);
+
+        // The flag only accepts 0 and 1
+        assert_eq!(
+            assign(&mut params, "source.en", Value::U8(2)),
+            Err(Error::InvalidValue)
+        );
+        assert_eq!(assign(&mut params, "source.val", Value::U8(2)), Ok(()));
+        assert_eq!(params.source.get(), None);
+        assert_eq!(assign(&mut params, "source.en", Value::U8(1)), Ok(()));
+        assert_eq!(params.source.get(), Some(&Source::Vision));
+    }
+
+    #[test]
+    fn disable_write_enable() {
+        let mut params = Params {
+            limit: Optional::some(2.5),
+            lpf: Optional::some(Filter {
+                cutoff: 30.0,
+                order: 2,
+            }),
+            ..Default::default()
+        };
+
+        // Disabling keeps the value, which can still be written
+        assert_eq!(assign(&mut params, "limit.en", Value::U8(0)), Ok(()));
+        assert_eq!(params.limit.to_option(), None);
+        assert_eq!(get_value(&params, "limit.val"), Some(Value::F32(2.5)));
+        assert_eq!(assign(&mut params, "limit.val", Value::F32(4.0)), Ok(()));
+
+        // Enabling again restores the last value
+        assert_eq!(assign(&mut params, "limit.en", Value::U8(1)), Ok(()));
+        assert_eq!(get_value(&params, "limit.val"), Some(Value::F32(4.0)));
+        assert_eq!(Option::from(params.limit), Some(4.0));
+
+        // Trees behave the same
+        assert_eq!(assign(&mut params, "lpf.en", Value::U8(0)), Ok(()));
+        assert_eq!(assign(&mut params, "lpf.val.order", Value::U8(4)), Ok(()));
+        assert_eq!(assign(&mut params, "lpf.en", Value::U8(1)), Ok(()));
+        assert_eq!(
+            params.lpf.get(),
+            Some(&Filter {
+                cutoff: 30.0,
+                order: 4
+            })
+        );
+    }
+
+    #[test]
+    fn storage_round_trip() {
+        let stored = Params {
+            rate: 10,
+            limit: Optional::none(1.5),
+            source: Some(Source::Vision).into(),
+            ..Default::default()
+        };
+        let mut storage = mav_param::storage::MemoryStorage::<8>::new();
+        mav_param::storage::save(&stored, &mut storage).unwrap();
+
+        // Both the flags and the kept values are restored
+        let mut params = Params {
+            limit: Optional::some(1.0),
+            ..Default::default()
+        };
+        assert_eq!(mav_param::storage::load(&mut params, &mut storage), Ok(8));
+        assert_eq!(params.limit, Optional::none(1.5));
+        assert_eq!(params.source.to_option(), Some(Source::Vision));
+    }
+
+    fn listed(params: &dyn Tree) -> Vec<(String, Value)> {
+        param_iter(params)
+            .flatten()
+            .map(|p| (p.ident.as_str().to_owned(), p.value))
+            .collect()
+    }
+
+    #[test]
+    fn native_option() {
+        let mut params = Native {
+            limit: Some(2.5),
+            ..Default::default()
+        };
+
+        // Absent leaves read as their default, and absent trees are not listed
+        assert_eq!(
+            listed(&params),
+            [
+                ("limit.en".to_owned(), Value::U8(1)),
+                ("limit.val".to_owned(), Value::F32(2.5)),
+                ("source.en".to_owned(), Value::U8(0)),
+                ("source.val".to_owned(), Value::U8(1)),
+                ("lpf.en".to_owned(), Value::U8(0)),
+            ]
+        );
+
+        // Resolving an absent value does not make it present, nor does a rejected write
+        assert_eq!(
+            assign(&mut params, "source.val", Value::F32(2.0)),
+            Err(Error::TypeMismatch {
+                expected: mav_param::ParamType::Uint8,
+                received: mav_param::ParamType::Real32,
+            })
+        );
+        assert_eq!(params.source, None);
+
+        // Writing an absent leaf makes it present
+        assert_eq!(assign(&mut params, "source.val", Value::U8(2)), Ok(()));
+        assert_eq!(params.source, Some(Source::Vision));
+
+        // Disabling drops the value, and enabling sets the default
+        assert_eq!(
+            assign(&mut params, "limit.en", Value::U8(2)),
+            Err(Error::InvalidValue)
+        );
+        assert_eq!(assign(&mut params, "limit.en", Value::U8(0)), Ok(()));
+        assert_eq!(params.limit, None);
+        assert_eq!(get_value(&params, "limit.val"), Some(Value::F32(0.0)));
+        assert_eq!(assign(&mut params, "limit.en", Value::U8(1)), Ok(()));
+        assert_eq!(params.limit, Some(0.0));
+
+        // Trees are listed once present
+        assert_eq!(
+            assign(&mut params, "lpf.val.order", Value::U8(2)),
+            Err(Error::NoSuchParameter)
+        );
+        assert_eq!(param_count(&params), 5);
+        assert_eq!(assign(&mut params, "lpf.en", Value::U8(1)), Ok(()));
+        assert_eq!(assign(&mut params, "lpf.val.order", Value::U8(2)), Ok(()));
+        assert_eq!(param_count(&params), 7);
+        assert_eq!(
+            params.lpf,
+            Some(Filter {
+                cutoff: 0.0,
+                order: 2
+            })
+        );
+    }
+
+    #[test]
+    fn native_rollback() {
+        let mut params = Native {
+            limit: Some(2.5),
+            ..Default::default()
+        };
+
+        // Disabling drops the value, which is restored when the transaction fails
+        let mut transaction = mav_param::transaction::Transaction::<4>::new();
+        for (ident, value) in [("limit.en", Value::U8(0)), ("lpf.en", Value::U8(2))] {
+            let ident = mav_param::Ident::from_str_truncated(ident);
+            transaction
+                .stage(mav_param::Parameter { ident, value })
+                .unwrap();
+        }
+        assert!(transaction.commit(&mut params).is_err());
+        assert_eq!(params.limit, Some(2.5));
+    }
+}
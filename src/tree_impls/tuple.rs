@@ -15,6 +15,20 @@ impl<T0: Node> Tree for (T0,) {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.0.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.0.node_mut()),
+            _ => None,
+        }
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0"]
     }
@@ -37,6 +51,22 @@ impl<T0: Node, T1: Node> Tree for (T0, T1) {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.0.node_ref()),
+            1 => Some(self.1.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.0.node_mut()),
+            1 => Some(self.1.node_mut()),
+            _ => None,
+        }
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1"]
     }
@@ -61,6 +91,24 @@ impl<T0: Node, T1: Node, T2: Node> Tree for (T0, T1, T2) {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.0.node_ref()),
+            1 => Some(self.1.node_ref()),
+            2 => Some(self.2.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.0.node_mut()),
+            1 => Some(self.1.node_mut()),
+            2 => Some(self.2.node_mut()),
+            _ => None,
+        }
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2"]
     }
@@ -87,6 +135,26 @@ impl<T0: Node, T1: Node, T2: Node, T3: Node> Tree for (T0, T1, T2, T3) {
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.0.node_ref()),
+            1 => Some(self.1.node_ref()),
+            2 => Some(self.2.node_ref()),
+            3 => Some(self.3.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.0.node_mut()),
+            1 => Some(self.1.node_mut()),
+            2 => Some(self.2.node_mut()),
+            3 => Some(self.3.node_mut()),
+            _ => None,
+        }
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3"]
     }
@@ -115,6 +183,28 @@ impl<T0: Node, T1: Node, T2: Node, T3: Node, T4: Node> Tree for (T0, T1, T2, T3,
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.0.node_ref()),
+            1 => Some(self.1.node_ref()),
+            2 => Some(self.2.node_ref()),
+            3 => Some(self.3.node_ref()),
+            4 => Some(self.4.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.0.node_mut()),
+            1 => Some(self.1.node_mut()),
+            2 => Some(self.2.node_mut()),
+            3 => Some(self.3.node_mut()),
+            4 => Some(self.4.node_mut()),
+            _ => None,
+        }
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4"]
     }
@@ -145,6 +235,30 @@ impl<T0: Node, T1: Node, T2: Node, T3: Node, T4: Node, T5: Node> Tree for (T0, T
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.0.node_ref()),
+            1 => Some(self.1.node_ref()),
+            2 => Some(self.2.node_ref()),
+            3 => Some(self.3.node_ref()),
+            4 => Some(self.4.node_ref()),
+            5 => Some(self.5.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.0.node_mut()),
+            1 => Some(self.1.node_mut()),
+            2 => Some(self.2.node_mut()),
+            3 => Some(self.3.node_mut()),
+            4 => Some(self.4.node_mut()),
+            5 => Some(self.5.node_mut()),
+            _ => None,
+        }
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4", "5"]
     }
@@ -179,6 +293,32 @@ impl<T0: Node, T1: Node, T2: Node, T3: Node, T4: Node, T5: Node, T6: Node> Tree
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.0.node_ref()),
+            1 => Some(self.1.node_ref()),
+            2 => Some(self.2.node_ref()),
+            3 => Some(self.3.node_ref()),
+            4 => Some(self.4.node_ref()),
+            5 => Some(self.5.node_ref()),
+            6 => Some(self.6.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.0.node_mut()),
+            1 => Some(self.1.node_mut()),
+            2 => Some(self.2.node_mut()),
+            3 => Some(self.3.node_mut()),
+            4 => Some(self.4.node_mut()),
+            5 => Some(self.5.node_mut()),
+            6 => Some(self.6.node_mut()),
+            _ => None,
+        }
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4", "5", "6"]
     }
@@ -215,6 +355,34 @@ impl<T0: Node, T1: Node, T2: Node, T3: Node, T4: Node, T5: Node, T6: Node, T7: N
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.0.node_ref()),
+            1 => Some(self.1.node_ref()),
+            2 => Some(self.2.node_ref()),
+            3 => Some(self.3.node_ref()),
+            4 => Some(self.4.node_ref()),
+            5 => Some(self.5.node_ref()),
+            6 => Some(self.6.node_ref()),
+            7 => Some(self.7.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.0.node_mut()),
+            1 => Some(self.1.node_mut()),
+            2 => Some(self.2.node_mut()),
+            3 => Some(self.3.node_mut()),
+            4 => Some(self.4.node_mut()),
+            5 => Some(self.5.node_mut()),
+            6 => Some(self.6.node_mut()),
+            7 => Some(self.7.node_mut()),
+            _ => None,
+        }
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4", "5", "6", "7"]
     }
@@ -253,6 +421,36 @@ impl<T0: Node, T1: Node, T2: Node, T3: Node, T4: Node, T5: Node, T6: Node, T7: N
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.0.node_ref()),
+            1 => Some(self.1.node_ref()),
+            2 => Some(self.2.node_ref()),
+            3 => Some(self.3.node_ref()),
+            4 => Some(self.4.node_ref()),
+            5 => Some(self.5.node_ref()),
+            6 => Some(self.6.node_ref()),
+            7 => Some(self.7.node_ref()),
+            8 => Some(self.8.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.0.node_mut()),
+            1 => Some(self.1.node_mut()),
+            2 => Some(self.2.node_mut()),
+            3 => Some(self.3.node_mut()),
+            4 => Some(self.4.node_mut()),
+            5 => Some(self.5.node_mut()),
+            6 => Some(self.6.node_mut()),
+            7 => Some(self.7.node_mut()),
+            8 => Some(self.8.node_mut()),
+            _ => None,
+        }
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4", "5", "6", "7", "8"]
     }
@@ -303,6 +501,38 @@ impl<
         }
     }
 
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        match index {
+            0 => Some(self.0.node_ref()),
+            1 => Some(self.1.node_ref()),
+            2 => Some(self.2.node_ref()),
+            3 => Some(self.3.node_ref()),
+            4 => Some(self.4.node_ref()),
+            5 => Some(self.5.node_ref()),
+            6 => Some(self.6.node_ref()),
+            7 => Some(self.7.node_ref()),
+            8 => Some(self.8.node_ref()),
+            9 => Some(self.9.node_ref()),
+            _ => None,
+        }
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        match index {
+            0 => Some(self.0.node_mut()),
+            1 => Some(self.1.node_mut()),
+            2 => Some(self.2.node_mut()),
+            3 => Some(self.3.node_mut()),
+            4 => Some(self.4.node_mut()),
+            5 => Some(self.5.node_mut()),
+            6 => Some(self.6.node_mut()),
+            7 => Some(self.7.node_mut()),
+            8 => Some(self.8.node_mut()),
+            9 => Some(self.9.node_mut()),
+            _ => None,
+        }
+    }
+
     fn entries(&self) -> &'static [&'static str] {
         &["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]
     }
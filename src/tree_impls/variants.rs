@@ -0,0 +1,206 @@
+use crate::{Error, Node, NodeMut, NodeRef, ParamType, Tree, Validated, Value, ValueMut};
+
+/// An enum deriving [`Tree`] with `#[tree(all_variants)]`, whose fields can be held for
+/// every variant by [`Variants`].
+pub trait AllVariants: Sized {
+    /// The index of the selected variant and the fields of every variant
+    type Store: Tree + Default + Clone;
+
+    /// Select the variant, and store its fields
+    fn store(self, store: &mut Self::Store);
+
+    /// Create the selected variant from the stored fields
+    fn load(store: &Self::Store) -> Self;
+}
+
+/// An enum which holds the fields of every variant, rather than only of the active one.
+///
+/// It is listed as the tag of the enum, followed by the fields of every variant, so the
+/// parameter count does not depend on the selected variant. All fields can be written,
+/// and writing the tag only selects another variant, keeping the fields of all of them.
+pub struct Variants<E: AllVariants> {
+    store: E::Store,
+}
+
+impl<E: AllVariants> Variants<E> {
+    /// Create the variants with the fields of the given one, and defaults for the others
+    pub fn new(value: E) -> Self {
+        let mut store = E::Store::default();
+        value.store(&mut store);
+        Self { store }
+    }
+
+    /// Get the selected variant
+    #[must_use]
+    pub fn get(&self) -> E {
+        E::load(&self.store)
+    }
+
+    /// Select the given variant and store its fields, keeping the fields of the others
+    pub fn set(&mut self, value: E) {
+        value.store(&mut self.store);
+    }
+}
+
+impl<E: AllVariants> Default for Variants<E> {
+    fn default() -> Self {
+        Self {
+            store: E::Store::default(),
+        }
+    }
+}
+
+impl<E: AllVariants> Clone for Variants<E> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+        }
+    }
+}
+
+impl<E: AllVariants> From<E> for Variants<E> {
+    fn from(value: E) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<E: AllVariants> Tree for Variants<E> {
+    fn get_ref<'a>(&'a self, path: &str) -> Option<NodeRef<'a>> {
+        self.store.get_ref(path)
+    }
+
+    fn get_mut<'a>(&'a mut self, path: &str) -> Option<NodeMut<'a>> {
+        self.store.get_mut(path)
+    }
+
+    fn get_ref_at(&self, index: usize) -> Option<NodeRef<'_>> {
+        self.store.get_ref_at(index)
+    }
+
+    fn get_mut_at(&mut self, index: usize) -> Option<NodeMut<'_>> {
+        self.store.get_mut_at(index)
+    }
+
+    fn entries(&self) -> &'static [&'static str] {
+        self.store.entries()
+    }
+}
+
+// The index of the selected variant of a store, out of `N` variants
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VariantTag<const N: usize>(pub u8);
+
+impl<const N: usize> Validated for VariantTag<N> {
+    fn value(&self) -> Value {
+        Value::U8(self.0)
+    }
+
+    fn assign(&mut self, value: Value) -> Result<(), Error> {
+        match value {
+            Value::U8(index) if usize::from(index) < N => self.0 = index,
+            Value::U8(_) => return Err(Error::InvalidValue),
+            _ => {
+                return Err(Error::TypeMismatch {
+                    expected: ParamType::Uint8,
+                    received: value.param_type(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Node for VariantTag<N> {
+    fn node_ref(&self) -> NodeRef<'_> {
+        NodeRef::Value(self.value())
+    }
+
+    fn node_mut(&mut self) -> NodeMut<'_> {
+        NodeMut::Value(ValueMut::Validated(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Variants;
+    use crate as mav_param;
+    use mav_param::{Error, Tree, Value, get_value_mut, param_count, param_iter};
+
+    #[derive(Tree, Default, Debug, PartialEq, Clone)]
+    struct EkfCfg {
+        q: f32,
+        r: f32,
+    }
+
+    #[derive(Tree, Debug, PartialEq)]
+    #[tree(all_variants, tag = "type")]
+    enum Estimator {
+        Ekf(EkfCfg),
+        Complementary { gain: f32, bias: bool },
+        Disabled,
+    }
+
+    #[derive(Tree, Default)]
+    struct Params {
+        est: Variants<Estimator>,
+    }
+
+    fn assign(params: &mut Params, ident: &str, value: Value) -> Result<(), Error> {
+        get_value_mut(params, ident)
+            .ok_or(Error::NoSuchParameter)?
+            .assign(value)
+    }
+
+    #[test]
+    fn all_variants() {
+        let mut params = Params {
+            est: Estimator::Complementary {
+                gain: 0.5,
+                bias: true,
+            }
+            .into(),
+        };
+
+        // The fields of every variant are listed, including trees
+        let listed: Vec<_> = param_iter(&params)
+            .flatten()
+            .map(|p| (p.ident.as_str().to_owned(), p.value))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("est.type".to_owned(), Value::U8(1)),
+                ("est.ekf.q".to_owned(), Value::F32(0.0)),
+                ("est.ekf.r".to_owned(), Value::F32(0.0)),
+                ("est.gain".to_owned(), Value::F32(0.5)),
+                ("est.bias".to_owned(), Value::U8(1)),
+            ]
+        );
+
+        // Fields of inactive variants can be written, and are kept when switching
+        assert_eq!(assign(&mut params, "est.ekf.r", Value::F32(2.0)), Ok(()));
+        assert_eq!(
+            assign(&mut params, "est.type", Value::U8(3)),
+            Err(Error::InvalidValue)
+        );
+        assert_eq!(assign(&mut params, "est.type", Value::U8(0)), Ok(()));
+        assert_eq!(params.est.get(), Estimator::Ekf(EkfCfg { q: 0.0, r: 2.0 }));
+        assert_eq!(param_count(&params), 5);
+
+        assert_eq!(assign(&mut params, "est.type", Value::U8(1)), Ok(()));
+        assert_eq!(
+            params.est.get(),
+            Estimator::Complementary {
+                gain: 0.5,
+                bias: true
+            }
+        );
+
+        // Setting a variant keeps the fields of the others
+        params.est.set(Estimator::Disabled);
+        assert_eq!(params.est.get(), Estimator::Disabled);
+        assert_eq!(assign(&mut params, "est.type", Value::U8(0)), Ok(()));
+        assert_eq!(params.est.get(), Estimator::Ekf(EkfCfg { q: 0.0, r: 2.0 }));
+    }
+}
@@ -1,87 +1,222 @@
+use crate::Error;
+
 ///  Primitives which can be represented as a "float" in a Mavlink parameter
 ///
-/// This trait enables conversion between MAVLink parameter values (which are transmitted
-/// as IEEE 754 floats) and their actual primitive types through bytewise reinterpretation
-/// rather than numeric conversion.
-pub trait Primitive {
+/// This trait enables conversion between `MAVLink` parameter values (which are transmitted
+/// as IEEE 754 floats) and their actual primitive types, either through bytewise
+/// reinterpretation or through numeric conversion, see [`Encoding`].
+pub trait Primitive: Sized {
     fn from_bytewise(val: f32) -> Self;
     fn into_bytewise(self) -> f32;
     fn into_value(self) -> Value;
+
+    /// Numerically convert the float into this type.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::OutOfRange`] if the value does not fit in this type,
+    /// and [`Error::PrecisionLoss`] if it is not a whole number.
+    fn from_c_cast(val: f32) -> Result<Self, Error>;
+
+    /// Numerically convert this value into a float.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PrecisionLoss`] if the value cannot be represented exactly.
+    fn into_c_cast(self) -> Result<f32, Error>;
+}
+
+/// The encoding of a value into the float field of a Mavlink parameter message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Encoding {
+    /// The bytes of the value are reinterpreted as a float,
+    /// corresponds to `MAV_PROTOCOL_CAPABILITY_PARAM_ENCODE_BYTEWISE`.
+    #[default]
+    Bytewise,
+    /// The value is numerically converted into a float,
+    /// corresponds to `MAV_PROTOCOL_CAPABILITY_PARAM_ENCODE_C_CAST`.
+    CCast,
+}
+
+/// The type of a parameter, corresponds to `MAV_PARAM_TYPE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum ParamType {
+    Uint8 = 1,
+    Int8 = 2,
+    Uint16 = 3,
+    Int16 = 4,
+    Uint32 = 5,
+    Int32 = 6,
+    Uint64 = 7,
+    Int64 = 8,
+    Real32 = 9,
+    Real64 = 10,
+}
+
+impl TryFrom<u8> for ParamType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            1 => ParamType::Uint8,
+            2 => ParamType::Int8,
+            3 => ParamType::Uint16,
+            4 => ParamType::Int16,
+            5 => ParamType::Uint32,
+            6 => ParamType::Int32,
+            7 => ParamType::Uint64,
+            8 => ParamType::Int64,
+            9 => ParamType::Real32,
+            10 => ParamType::Real64,
+            _ => return Err(Error::UnsupportedType),
+        })
+    }
+}
+
+/// A parameter which only accepts some values of its type, like an enum whose
+/// discriminant is exposed as an integer.
+///
+/// Mutable access to such parameters is given by [`ValueMut::Validated`], so that
+/// every write goes through [`Validated::assign`].
+pub trait Validated: Send + Sync {
+    /// The current value of the parameter
+    fn value(&self) -> Value;
+
+    /// Assign the value to the parameter.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TypeMismatch`] if the type does not match the type of
+    /// [`Validated::value`], and [`Error::InvalidValue`] if the value is not accepted.
+    /// The parameter must then be unchanged.
+    fn assign(&mut self, value: Value) -> Result<(), Error>;
+
+    /// Returns `true` if assigning another value changes the parameters next to this one,
+    /// like the tag of an enum, which selects the variant and resets its fields.
+    fn selects_variant(&self) -> bool {
+        false
+    }
+}
+
+impl core::fmt::Debug for dyn Validated + '_ {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.value().fmt(f)
+    }
+}
+
+impl PartialEq for dyn Validated + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.value() == other.value()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for dyn Validated + '_ {
+    fn format(&self, fmt: defmt::Formatter) {
+        self.value().format(fmt);
+    }
 }
 
 /// Converts the float-encoded value into the correct primitive type.
-#[cfg(target_endian = "little")]
+#[must_use]
 pub fn from_bytewise<F: Primitive>(val: f32) -> F {
     F::from_bytewise(val)
 }
 
 /// Converts the primite value into the float-encoded equivalent.
-#[cfg(target_endian = "little")]
 pub fn into_bytewise<F: Primitive>(val: F) -> f32 {
     F::into_bytewise(val)
 }
 
+/// Numerically converts the float value into the correct primitive type.
+///
+/// # Errors
+///
+/// See [`Primitive::from_c_cast`].
+pub fn from_c_cast<F: Primitive>(val: f32) -> Result<F, Error> {
+    F::from_c_cast(val)
+}
+
+/// Numerically converts the primitive value into a float.
+///
+/// # Errors
+///
+/// See [`Primitive::into_c_cast`].
+pub fn into_c_cast<F: Primitive>(val: F) -> Result<f32, Error> {
+    F::into_c_cast(val)
+}
+
 macro_rules! impl_primitive {
     (
-        $( $variant:ident($type:ident) ),+ $(,)?
+        $( $(#[$attr:meta])* $variant:ident($type:ident) ),+ $(,)?
     ) => {
 
         /// Represents the value of a parameter.
+        ///
+        /// Note: The 64-bit variants only exist with the `ext` feature.
         #[derive(Debug, Clone, Copy, PartialEq)]
+        #[non_exhaustive]
         #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
         #[cfg_attr(feature = "defmt", derive(defmt::Format))]
         pub enum Value {
-            $( $variant($type), )+
+            $( $(#[$attr])* $variant($type), )+
         }
 
         /// Represents a mutable reference to some parameters value.
         #[derive(Debug, PartialEq)]
+        #[non_exhaustive]
         #[cfg_attr(feature = "defmt", derive(defmt::Format))]
         pub enum ValueMut<'a> {
-            $( $variant(&'a mut $type), )+
+            $( $(#[$attr])* $variant(&'a mut $type), )+
+            /// A parameter which only accepts some values of its type
+            Validated(&'a mut dyn Validated),
         }
 
-        // An unsafe type that allows for byte-wise conversion between the inner types.
-        //
-        // This matches the Mavlink C API, and is why we do the same here. It is however
-        // only safe to use on little-endian systems. PRs for big-endian support are welcome.
-        #[repr(C)]
-        #[cfg(target_endian = "little")]
-        union Bytewise {
-            $( $type: $type, )+
-        }
-
-        $( impl Primitive for $type {
-            #[inline(always)]
-            #[cfg(target_endian = "little")]
-            fn from_bytewise(val: f32) -> $type {
-                unsafe { Bytewise { f32: val }.$type }
-            }
-
-            #[inline(always)]
-            #[cfg(target_endian = "little")]
-            fn into_bytewise(self) -> f32 {
-                unsafe { Bytewise { $type: self }.f32 }
-            }
-
-            fn into_value(self) -> Value {
-                Value::$variant(self)
-            }
-        } )+
-
         impl Value {
             /// Convert this [`Value`] into a [`ValueMut`] to allow per-type mutation.
             pub fn as_mut(&mut self) -> ValueMut<'_> {
                 match self {
-                    $( Value::$variant(v) => ValueMut::$variant(v), )+
+                    $( $(#[$attr])* Value::$variant(v) => ValueMut::$variant(v), )+
                 }
             }
 
             /// Get the Mavlink-compatible bytewise representation of this [`Value`]
-            #[cfg(target_endian = "little")]
             pub fn into_bytewise(&self) -> f32 {
                 match self {
-                    $( Value::$variant(v) => v.into_bytewise(), )+
+                    $( $(#[$attr])* Value::$variant(v) => v.into_bytewise(), )+
+                }
+            }
+
+            /// Get the Mavlink-compatible representation of this [`Value`] using the given encoding
+            ///
+            /// # Errors
+            ///
+            /// Returns [`Error::PrecisionLoss`] if the value cannot be represented
+            /// exactly using [`Encoding::CCast`], and [`Error::OutOfRange`] if a 64-bit
+            /// integer does not fit in the 32 bits of [`Encoding::Bytewise`]. A 64-bit
+            /// float is converted in both encodings, so it is checked the same way.
+            pub fn encode(&self, encoding: Encoding) -> Result<f32, Error> {
+                match encoding {
+                    Encoding::Bytewise => {
+                        // The 64-bit integers only keep their lower 32 bits, while the
+                        // 64-bit float is converted, so it must be representable as a `f32`
+                        #[cfg(feature = "ext")]
+                        match *self {
+                            Value::U64(v) if u32::try_from(v).is_err() => return Err(Error::OutOfRange),
+                            Value::I64(v) if i32::try_from(v).is_err() => return Err(Error::OutOfRange),
+                            Value::F64(v) => return v.into_c_cast(),
+                            _ => {}
+                        }
+                        Ok(self.into_bytewise())
+                    }
+                    Encoding::CCast => match self {
+                        $( $(#[$attr])* Value::$variant(v) => v.into_c_cast(), )+
+                    },
                 }
             }
         }
@@ -90,36 +225,311 @@ macro_rules! impl_primitive {
             /// Obtain an owned version of this value.
             pub fn owned(&self) -> Value {
                 match &self {
-                    $( ValueMut::$variant(vm) => vm.into_value(), )+
+                    $( $(#[$attr])* ValueMut::$variant(vm) => vm.into_value(), )+
+                    ValueMut::Validated(vm) => vm.value(),
                 }
             }
 
             /// Get the Mavlink-compatible bytewise representation of this [`ValueMut`]
-            #[cfg(target_endian = "little")]
             pub fn into_bytewise(&self) -> f32 {
                 self.owned().into_bytewise()
             }
 
-            /// Attempt to assign another [`Value`] to this [`ValueMut`] without changing its type.
+            /// Get the Mavlink-compatible representation of this [`ValueMut`] using the given encoding
             ///
-            /// If the types matched this functions returns `true`, otherwise `false`.
-            pub fn try_assign(&mut self, other: Value) -> bool {
+            /// # Errors
+            ///
+            /// See [`Value::encode`].
+            pub fn encode(&self, encoding: Encoding) -> Result<f32, Error> {
+                self.owned().encode(encoding)
+            }
+
+            /// Decode the Mavlink-compatible float using the given encoding, and assign it
+            /// to this [`ValueMut`] without changing its type.
+            ///
+            /// # Errors
+            ///
+            /// Returns [`Error::OutOfRange`] or [`Error::PrecisionLoss`] if the value does
+            /// not fit exactly in the type using [`Encoding::CCast`], and [`Error::InvalidValue`]
+            /// if a validated parameter rejects it. The value is then unchanged.
+            pub fn assign_encoded(&mut self, val: f32, encoding: Encoding) -> Result<(), Error> {
+                match self {
+                    $( $(#[$attr])* ValueMut::$variant(vm) => **vm = decode(val, encoding)?, )+
+                    ValueMut::Validated(vm) => {
+                        let param_type = vm.value().param_type();
+                        vm.assign(Value::from_wire_encoded(val, param_type, encoding)?)?;
+                    }
+                }
+                Ok(())
+            }
+
+            /// Assign another [`Value`] to this [`ValueMut`] without changing its type.
+            ///
+            /// # Errors
+            ///
+            /// Returns [`Error::TypeMismatch`] if the types do not match, and
+            /// [`Error::InvalidValue`] if a validated parameter rejects the value.
+            /// The value is then unchanged.
+            pub fn assign(&mut self, other: Value) -> Result<(), Error> {
                 match (self, other) {
-                    $( (ValueMut::$variant(vm), Value::$variant(v)) => **vm = v, )+
-                    _ => return false, // Type mismatch
+                    $( $(#[$attr])* (ValueMut::$variant(vm), Value::$variant(v)) => **vm = v, )+
+                    (ValueMut::Validated(vm), v) => vm.assign(v)?,
+                    (vm, v) => {
+                        return Err(Error::TypeMismatch {
+                            expected: vm.param_type(),
+                            received: v.param_type(),
+                        });
+                    }
                 }
-                true
+                Ok(())
+            }
+
+            /// Attempt to assign another [`Value`] to this [`ValueMut`] without changing its type.
+            ///
+            /// If the value was assigned this functions returns `true`, otherwise `false`.
+            pub fn try_assign(&mut self, other: Value) -> bool {
+                self.assign(other).is_ok()
+            }
+        }
+    };
+}
+
+// Numeric conversion for integers of up to 32 bits, which always fit in a f64
+macro_rules! int_c_cast {
+    ($type:ident) => {
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::float_cmp
+        )]
+        fn from_c_cast(val: f32) -> Result<$type, Error> {
+            let wide = f64::from(val);
+            if !(f64::from($type::MIN)..=f64::from($type::MAX)).contains(&wide) {
+                return Err(Error::OutOfRange);
+            }
+
+            let int = val as $type;
+            if f64::from(int) == wide {
+                Ok(int)
+            } else {
+                Err(Error::PrecisionLoss)
+            }
+        }
+
+        #[allow(clippy::cast_precision_loss, clippy::cast_lossless, clippy::float_cmp)]
+        fn into_c_cast(self) -> Result<f32, Error> {
+            let val = self as f32;
+            if f64::from(val) == f64::from(self) {
+                Ok(val)
+            } else {
+                Err(Error::PrecisionLoss)
             }
         }
     };
 }
 
+// Numeric conversion for floats is the identity
+macro_rules! float_c_cast {
+    ($type:ident) => {
+        fn from_c_cast(val: f32) -> Result<$type, Error> {
+            Ok(val)
+        }
+
+        fn into_c_cast(self) -> Result<f32, Error> {
+            Ok(self)
+        }
+    };
+}
+
+macro_rules! impl_bytewise {
+    (
+        $( $variant:ident($type:ident) => $c_cast:ident ),+ $(,)?
+    ) => {
+
+        // The byte-wise conversion places the little-endian bytes of the value at the start
+        // of the little-endian bytes of the float, with any remaining bytes set to zero.
+        //
+        // This matches the Mavlink wire format and the union used by the Mavlink C API
+        // on little-endian systems, while behaving identically on big-endian systems.
+        $( impl Primitive for $type {
+            #[inline(always)]
+            fn from_bytewise(val: f32) -> $type {
+                const LEN: usize = core::mem::size_of::<$type>();
+                let mut bytes = [0u8; LEN];
+                bytes.copy_from_slice(&val.to_le_bytes()[..LEN]);
+                $type::from_le_bytes(bytes)
+            }
+
+            #[inline(always)]
+            fn into_bytewise(self) -> f32 {
+                let mut bytes = [0u8; 4];
+                let le_bytes = self.to_le_bytes();
+                bytes[..le_bytes.len()].copy_from_slice(&le_bytes);
+                f32::from_le_bytes(bytes)
+            }
+
+            fn into_value(self) -> Value {
+                Value::$variant(self)
+            }
+
+            $c_cast!($type);
+        } )+
+    };
+}
+
+// Decode the float using the given encoding
+fn decode<F: Primitive>(val: f32, encoding: Encoding) -> Result<F, Error> {
+    match encoding {
+        Encoding::Bytewise => Ok(F::from_bytewise(val)),
+        Encoding::CCast => F::from_c_cast(val),
+    }
+}
+
+impl Value {
+    /// Get the Mavlink parameter type of this [`Value`]
+    #[must_use]
+    pub fn param_type(&self) -> ParamType {
+        match self {
+            Value::U8(_) => ParamType::Uint8,
+            Value::I8(_) => ParamType::Int8,
+            Value::U16(_) => ParamType::Uint16,
+            Value::I16(_) => ParamType::Int16,
+            Value::U32(_) => ParamType::Uint32,
+            Value::I32(_) => ParamType::Int32,
+            Value::F32(_) => ParamType::Real32,
+            #[cfg(feature = "ext")]
+            Value::U64(_) => ParamType::Uint64,
+            #[cfg(feature = "ext")]
+            Value::I64(_) => ParamType::Int64,
+            #[cfg(feature = "ext")]
+            Value::F64(_) => ParamType::Real64,
+        }
+    }
+
+    /// Returns `true` if both the type and the bytes of the values are equal, unlike
+    /// [`PartialEq`] which never considers NaN equal to itself
+    pub(crate) fn bitwise_eq(self, other: Value) -> bool {
+        self.param_type() == other.param_type() && self.le_bytes() == other.le_bytes()
+    }
+
+    /// The little-endian bytes of this [`Value`], padded with zeros to 8 bytes
+    pub(crate) fn le_bytes(self) -> [u8; 8] {
+        let mut bytes = [0u8; 8];
+        let mut write = |le_bytes: &[u8]| bytes[..le_bytes.len()].copy_from_slice(le_bytes);
+
+        match self {
+            Value::U8(v) => write(&v.to_le_bytes()),
+            Value::I8(v) => write(&v.to_le_bytes()),
+            Value::U16(v) => write(&v.to_le_bytes()),
+            Value::I16(v) => write(&v.to_le_bytes()),
+            Value::U32(v) => write(&v.to_le_bytes()),
+            Value::I32(v) => write(&v.to_le_bytes()),
+            Value::F32(v) => write(&v.to_le_bytes()),
+            #[cfg(feature = "ext")]
+            Value::U64(v) => write(&v.to_le_bytes()),
+            #[cfg(feature = "ext")]
+            Value::I64(v) => write(&v.to_le_bytes()),
+            #[cfg(feature = "ext")]
+            Value::F64(v) => write(&v.to_le_bytes()),
+        }
+
+        bytes
+    }
+
+    /// Create a [`Value`] from the bytewise-encoded float and type of a Mavlink message
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedType`] for 64-bit types without the `ext` feature.
+    pub fn from_wire(val: f32, param_type: ParamType) -> Result<Value, Error> {
+        Self::from_wire_encoded(val, param_type, Encoding::Bytewise)
+    }
+
+    /// Create a [`Value`] from the encoded float and type of a Mavlink message
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::UnsupportedType`] for 64-bit types without the `ext` feature,
+    /// and [`Error::OutOfRange`] or [`Error::PrecisionLoss`] if the value does not fit
+    /// exactly in the type using [`Encoding::CCast`].
+    pub fn from_wire_encoded(
+        val: f32,
+        param_type: ParamType,
+        encoding: Encoding,
+    ) -> Result<Value, Error> {
+        Ok(match param_type {
+            ParamType::Uint8 => Value::U8(decode(val, encoding)?),
+            ParamType::Int8 => Value::I8(decode(val, encoding)?),
+            ParamType::Uint16 => Value::U16(decode(val, encoding)?),
+            ParamType::Int16 => Value::I16(decode(val, encoding)?),
+            ParamType::Uint32 => Value::U32(decode(val, encoding)?),
+            ParamType::Int32 => Value::I32(decode(val, encoding)?),
+            ParamType::Real32 => Value::F32(decode(val, encoding)?),
+            #[cfg(feature = "ext")]
+            ParamType::Uint64 => Value::U64(decode(val, encoding)?),
+            #[cfg(feature = "ext")]
+            ParamType::Int64 => Value::I64(decode(val, encoding)?),
+            #[cfg(feature = "ext")]
+            ParamType::Real64 => Value::F64(decode(val, encoding)?),
+            #[cfg(not(feature = "ext"))]
+            ParamType::Uint64 | ParamType::Int64 | ParamType::Real64 => {
+                return Err(Error::UnsupportedType);
+            }
+        })
+    }
+}
+
+impl ValueMut<'_> {
+    /// Get the Mavlink parameter type of this [`ValueMut`]
+    #[must_use]
+    pub fn param_type(&self) -> ParamType {
+        self.owned().param_type()
+    }
+
+    /// Assign the bytewise-encoded float of a Mavlink message to this [`ValueMut`],
+    /// only if the type of the message matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TypeMismatch`] if the types do not match. The value is then unchanged.
+    pub fn set_from_wire(&mut self, val: f32, param_type: ParamType) -> Result<(), Error> {
+        self.set_from_wire_encoded(val, param_type, Encoding::Bytewise)
+    }
+
+    /// Assign the encoded float of a Mavlink message to this [`ValueMut`],
+    /// only if the type of the message matches.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::TypeMismatch`] if the types do not match, [`Error::OutOfRange`]
+    /// or [`Error::PrecisionLoss`] if the value does not fit exactly in the type using
+    /// [`Encoding::CCast`], and [`Error::InvalidValue`] if a validated parameter rejects it.
+    /// The value is then unchanged.
+    pub fn set_from_wire_encoded(
+        &mut self,
+        val: f32,
+        param_type: ParamType,
+        encoding: Encoding,
+    ) -> Result<(), Error> {
+        let expected = self.param_type();
+        if expected != param_type {
+            return Err(Error::TypeMismatch {
+                expected,
+                received: param_type,
+            });
+        }
+
+        self.assign_encoded(val, encoding)
+    }
+}
+
 // These primitive types are supported by MavLink.
 //
 // Technically, i64, u64 and f64 are also "supported", though they
-// need to fit in a 32-bit float when sent, so supporting them does
-// not make much sense. These can be transmitted losslessly while
-// being supported. Sadly, bools are not supported.
+// need to fit in a 32-bit float when sent, so supporting them only
+// makes sense for the extended parameter protocol, which is why they
+// are behind the `ext` feature. Sadly, bools are not supported, so
+// they are instead exposed as a validated `U8` of either 0 or 1.
 
 impl_primitive! {
     U8(u8),
@@ -129,4 +539,326 @@ impl_primitive! {
     U32(u32),
     I32(i32),
     F32(f32),
+    #[cfg(feature = "ext")]
+    U64(u64),
+    #[cfg(feature = "ext")]
+    I64(i64),
+    #[cfg(feature = "ext")]
+    F64(f64),
+}
+
+impl_bytewise! {
+    U8(u8) => int_c_cast,
+    I8(i8) => int_c_cast,
+    U16(u16) => int_c_cast,
+    I16(i16) => int_c_cast,
+    U32(u32) => int_c_cast,
+    I32(i32) => int_c_cast,
+    F32(f32) => float_c_cast,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Encoding, ParamType, Value, from_bytewise, from_c_cast, into_bytewise, into_c_cast,
+    };
+    use crate::Error;
+
+    #[test]
+    fn bytewise_layout() {
+        // The value bytes are placed first, with zero padding
+        assert_eq!(into_bytewise(0xABu8).to_le_bytes(), [0xAB, 0, 0, 0]);
+        assert_eq!(into_bytewise(-2i8).to_le_bytes(), [0xFE, 0, 0, 0]);
+        assert_eq!(into_bytewise(0x1234u16).to_le_bytes(), [0x34, 0x12, 0, 0]);
+        assert_eq!(into_bytewise(-2i16).to_le_bytes(), [0xFE, 0xFF, 0, 0]);
+        assert_eq!(
+            into_bytewise(0x1234_5678u32).to_le_bytes(),
+            0x1234_5678u32.to_le_bytes()
+        );
+        assert_eq!(into_bytewise(-2i32).to_le_bytes(), (-2i32).to_le_bytes());
+        assert_eq!(into_bytewise(1.5f32).to_bits(), 1.5f32.to_bits());
+
+        // Trailing bytes are ignored when decoding
+        let val = f32::from_le_bytes([0xFE, 0xFF, 0xAA, 0xBB]);
+        assert_eq!(from_bytewise::<u8>(val), 0xFE);
+        assert_eq!(from_bytewise::<i8>(val), -2);
+        assert_eq!(from_bytewise::<u16>(val), 0xFFFE);
+        assert_eq!(from_bytewise::<i16>(val), -2);
+        assert_eq!(from_bytewise::<u32>(val), 0xBBAA_FFFE);
+        assert_eq!(
+            from_bytewise::<i32>(val),
+            i32::from_le_bytes([0xFE, 0xFF, 0xAA, 0xBB])
+        );
+        assert_eq!(from_bytewise::<f32>(val).to_bits(), val.to_bits());
+    }
+
+    #[test]
+    fn bytewise_round_trip() {
+        let values = [
+            Value::U8(u8::MAX),
+            Value::I8(i8::MIN),
+            Value::U16(u16::MAX),
+            Value::I16(i16::MIN),
+            Value::U32(u32::MAX),
+            Value::I32(i32::MIN),
+            Value::F32(-0.125),
+        ];
+
+        for value in values {
+            let mut decoded = value;
+            let val = value.into_bytewise();
+            assert_eq!(
+                decoded.as_mut().assign_encoded(val, Encoding::Bytewise),
+                Ok(())
+            );
+            assert_eq!(decoded, value);
+        }
+    }
+
+    #[test]
+    fn c_cast_from_float() {
+        assert_eq!(from_c_cast::<u8>(255.0), Ok(255));
+        assert_eq!(from_c_cast::<u8>(256.0), Err(Error::OutOfRange));
+        assert_eq!(from_c_cast::<u8>(-1.0), Err(Error::OutOfRange));
+        assert_eq!(from_c_cast::<u8>(1.5), Err(Error::PrecisionLoss));
+        assert_eq!(from_c_cast::<i8>(-128.0), Ok(-128));
+        assert_eq!(from_c_cast::<i16>(f32::NAN), Err(Error::OutOfRange));
+        assert_eq!(from_c_cast::<i32>(f32::INFINITY), Err(Error::OutOfRange));
+        assert_eq!(from_c_cast::<u32>(4_294_967_040.0), Ok(4_294_967_040));
+        assert_eq!(from_c_cast::<u32>(4_294_967_296.0), Err(Error::OutOfRange));
+        assert_eq!(from_c_cast::<i32>(-2_147_483_648.0), Ok(i32::MIN));
+        assert_eq!(from_c_cast::<f32>(-0.25), Ok(-0.25));
+    }
+
+    #[test]
+    fn c_cast_into_float() {
+        assert_eq!(into_c_cast(200u8), Ok(200.0));
+        assert_eq!(into_c_cast(-30_000i16), Ok(-30_000.0));
+        assert_eq!(into_c_cast(16_777_216u32), Ok(16_777_216.0));
+        assert_eq!(into_c_cast(16_777_217u32), Err(Error::PrecisionLoss));
+        assert_eq!(into_c_cast(u32::MAX), Err(Error::PrecisionLoss));
+        assert_eq!(into_c_cast(i32::MIN), Ok(-2_147_483_648.0));
+        assert_eq!(into_c_cast(1.5f32), Ok(1.5));
+    }
+
+    #[test]
+    fn encode_and_assign() {
+        let mut value = Value::U16(1000);
+        assert_eq!(value.encode(Encoding::CCast), Ok(1000.0));
+        assert_eq!(
+            Value::U32(1000).encode(Encoding::Bytewise),
+            Ok(f32::from_bits(1000))
+        );
+
+        let mut value_mut = value.as_mut();
+        assert_eq!(value_mut.assign_encoded(42.0, Encoding::CCast), Ok(()));
+        assert_eq!(value_mut.owned(), Value::U16(42));
+
+        assert_eq!(
+            value_mut.assign_encoded(70_000.0, Encoding::CCast),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!(value_mut.owned(), Value::U16(42));
+
+        assert_eq!(
+            value_mut.assign_encoded(f32::from_bits(7), Encoding::Bytewise),
+            Ok(())
+        );
+        assert_eq!(value, Value::U16(7));
+
+        let mut value = Value::U32(0);
+        assert_eq!(
+            value.as_mut().assign_encoded(0.5, Encoding::CCast),
+            Err(Error::PrecisionLoss)
+        );
+        assert_eq!(
+            value.as_mut().assign_encoded(-1.0, Encoding::CCast),
+            Err(Error::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn param_type() {
+        for code in 0..=u8::MAX {
+            match ParamType::try_from(code) {
+                Ok(param_type) => assert_eq!(param_type as u8, code),
+                Err(error) => assert_eq!(error, Error::UnsupportedType),
+            }
+        }
+
+        let mut value = Value::I16(-5);
+        assert_eq!(value.param_type(), ParamType::Int16);
+        assert_eq!(value.as_mut().param_type(), ParamType::Int16);
+        assert_eq!(Value::F32(0.0).param_type(), ParamType::Real32);
+    }
+
+    #[test]
+    fn from_wire() {
+        let val = Value::U16(1234).into_bytewise();
+        assert_eq!(
+            Value::from_wire(val, ParamType::Uint16),
+            Ok(Value::U16(1234))
+        );
+        assert_eq!(
+            Value::from_wire_encoded(-3.0, ParamType::Int8, Encoding::CCast),
+            Ok(Value::I8(-3))
+        );
+        assert_eq!(
+            Value::from_wire_encoded(300.0, ParamType::Uint8, Encoding::CCast),
+            Err(Error::OutOfRange)
+        );
+
+        #[cfg(not(feature = "ext"))]
+        assert_eq!(
+            Value::from_wire(0.0, ParamType::Real64),
+            Err(Error::UnsupportedType)
+        );
+    }
+
+    #[test]
+    fn set_from_wire() {
+        let mut value = Value::U32(0);
+
+        let val = into_bytewise(0xDEAD_BEEFu32);
+        assert_eq!(value.as_mut().set_from_wire(val, ParamType::Uint32), Ok(()));
+        assert_eq!(value, Value::U32(0xDEAD_BEEF));
+
+        assert_eq!(
+            value.as_mut().set_from_wire(1.0, ParamType::Real32),
+            Err(Error::TypeMismatch {
+                expected: ParamType::Uint32,
+                received: ParamType::Real32,
+            })
+        );
+        assert_eq!(value, Value::U32(0xDEAD_BEEF));
+
+        assert_eq!(
+            value
+                .as_mut()
+                .set_from_wire_encoded(7.0, ParamType::Uint32, Encoding::CCast),
+            Ok(())
+        );
+        assert_eq!(value, Value::U32(7));
+    }
+
+    #[test]
+    fn validated_enum() {
+        use crate as mav_param;
+        use mav_param::{Node, Tree, ValueMut, get_value, get_value_mut};
+
+        #[derive(Node, Debug, PartialEq)]
+        #[repr(i16)]
+        enum Mode {
+            Manual = -1,
+            Stabilize = 0,
+            Auto = 4,
+        }
+
+        #[derive(Tree)]
+        struct Params {
+            mode: Mode,
+        }
+
+        let mut params = Params { mode: Mode::Manual };
+        assert_eq!(get_value(&params, "mode"), Some(Value::I16(-1)));
+
+        let mut value_mut = get_value_mut(&mut params, "mode").unwrap();
+        assert!(matches!(value_mut, ValueMut::Validated(_)));
+        assert_eq!(value_mut.param_type(), ParamType::Int16);
+        assert_eq!(value_mut.assign(Value::I16(4)), Ok(()));
+
+        // Values which are not a discriminant are rejected
+        assert_eq!(value_mut.assign(Value::I16(1)), Err(Error::InvalidValue));
+        assert_eq!(
+            value_mut.assign(Value::U8(0)),
+            Err(Error::TypeMismatch {
+                expected: ParamType::Int16,
+                received: ParamType::Uint8,
+            })
+        );
+        assert_eq!(
+            value_mut.set_from_wire_encoded(2.0, ParamType::Int16, Encoding::CCast),
+            Err(Error::InvalidValue)
+        );
+        assert_eq!(params.mode, Mode::Auto);
+
+        let mut value_mut = get_value_mut(&mut params, "mode").unwrap();
+        assert_eq!(
+            value_mut.set_from_wire_encoded(0.0, ParamType::Int16, Encoding::CCast),
+            Ok(())
+        );
+        assert_eq!(params.mode, Mode::Stabilize);
+    }
+
+    #[test]
+    fn enum_repr_hints() {
+        use crate as mav_param;
+        use mav_param::{Node, Validated};
+
+        // Only the integer type of the hints is used
+        #[derive(Node, Clone, Copy)]
+        #[repr(align(4), u16)]
+        enum Aligned {
+            Off = 0,
+            On = 300,
+        }
+
+        assert_eq!(Aligned::Off.value(), Value::U16(0));
+        assert_eq!(Aligned::On.value(), Value::U16(300));
+
+        #[cfg(feature = "ext")]
+        {
+            #[derive(Node, Clone, Copy)]
+            #[repr(i64, align(16))]
+            enum Wide {
+                Low = -1,
+                High = 1 << 40,
+            }
+
+            assert_eq!(Wide::Low.value(), Value::I64(-1));
+            assert_eq!(Wide::High.value(), Value::I64(1 << 40));
+        }
+    }
+
+    #[test]
+    fn bool_as_u8() {
+        use crate as mav_param;
+        use mav_param::{Tree, get_value, get_value_mut};
+
+        #[derive(Tree)]
+        struct Params {
+            armed: bool,
+            flags: (bool, bool),
+        }
+
+        let mut params = Params {
+            armed: true,
+            flags: (false, true),
+        };
+        assert_eq!(get_value(&params, "armed"), Some(Value::U8(1)));
+        assert_eq!(get_value(&params, "flags.0"), Some(Value::U8(0)));
+
+        let mut value_mut = get_value_mut(&mut params, "flags.0").unwrap();
+        assert_eq!(value_mut.param_type(), ParamType::Uint8);
+        assert_eq!(value_mut.assign(Value::U8(1)), Ok(()));
+
+        // Only 0 and 1 are accepted
+        assert_eq!(value_mut.assign(Value::U8(2)), Err(Error::InvalidValue));
+        assert_eq!(
+            value_mut.set_from_wire_encoded(0.5, ParamType::Uint8, Encoding::CCast),
+            Err(Error::PrecisionLoss)
+        );
+        assert_eq!(
+            value_mut.assign(Value::I8(0)),
+            Err(Error::TypeMismatch {
+                expected: ParamType::Uint8,
+                received: ParamType::Int8,
+            })
+        );
+        assert!(params.flags.0);
+
+        let mut value_mut = get_value_mut(&mut params, "armed").unwrap();
+        assert_eq!(value_mut.set_from_wire(0.0, ParamType::Uint8), Ok(()));
+        assert!(!params.armed);
+    }
 }
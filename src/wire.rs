@@ -0,0 +1,529 @@
+use crate::server::{Request, Response};
+use crate::{Encoding, Error, Ident, ParamType, Parameter, Value};
+
+const STX_V1: u8 = 0xFE;
+const STX_V2: u8 = 0xFD;
+
+const HEADER_LEN_V1: usize = 6;
+const HEADER_LEN_V2: usize = 10;
+const CHECKSUM_LEN: usize = 2;
+const SIGNATURE_LEN: usize = 13;
+
+// The only incompatibility flag we know of, signing is not verified
+const IFLAG_SIGNED: u8 = 0x01;
+
+/// The largest payload of the supported messages, which is `PARAM_VALUE`
+const MAX_PAYLOAD_LEN: usize = 25;
+
+/// The largest frame produced by [`encode`].
+pub const MAX_FRAME_LEN: usize = HEADER_LEN_V2 + MAX_PAYLOAD_LEN + CHECKSUM_LEN;
+
+/// The id, `CRC_EXTRA` and payload length of a message of the common dialect.
+struct MessageInfo {
+    id: u8,
+    crc_extra: u8,
+    len: usize,
+}
+
+const PARAM_REQUEST_READ: MessageInfo = MessageInfo {
+    id: 20,
+    crc_extra: 214,
+    len: 20,
+};
+
+const PARAM_REQUEST_LIST: MessageInfo = MessageInfo {
+    id: 21,
+    crc_extra: 159,
+    len: 2,
+};
+
+const PARAM_VALUE: MessageInfo = MessageInfo {
+    id: 22,
+    crc_extra: 220,
+    len: 25,
+};
+
+const PARAM_SET: MessageInfo = MessageInfo {
+    id: 23,
+    crc_extra: 168,
+    len: 23,
+};
+
+/// The version of the Mavlink frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Version {
+    V1,
+    /// Trailing zeros of the payload are truncated when encoding
+    V2,
+}
+
+/// The header fields of a Mavlink frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Header {
+    pub version: Version,
+    pub sequence: u8,
+    pub system_id: u8,
+    pub component_id: u8,
+}
+
+/// A parameter protocol message, which can be encoded into or decoded from a Mavlink frame.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Message {
+    /// Corresponds to `PARAM_REQUEST_LIST`, `PARAM_REQUEST_READ` or `PARAM_SET`.
+    Request {
+        target_system: u8,
+        target_component: u8,
+        request: Request,
+    },
+    /// Corresponds to `PARAM_VALUE`.
+    Value(Response),
+}
+
+/// Encode the message into a Mavlink frame, returning the length of the frame.
+///
+/// The value of `PARAM_SET` and `PARAM_VALUE` is encoded using the given [`Encoding`].
+/// Frames are never signed, so the frame is at most [`MAX_FRAME_LEN`] bytes.
+///
+/// # Errors
+///
+/// Returns [`Error::CapacityExceeded`] if the buffer is too small for the frame,
+/// and [`Error::OutOfRange`] or [`Error::PrecisionLoss`] if the value cannot be encoded.
+pub fn encode(
+    header: &Header,
+    message: &Message,
+    encoding: Encoding,
+    buf: &mut [u8],
+) -> Result<usize, Error> {
+    let mut payload = [0u8; MAX_PAYLOAD_LEN];
+    let info = message.write_payload(&mut payload, encoding)?;
+    let mut len = info.len;
+
+    let header_len = match header.version {
+        Version::V1 => HEADER_LEN_V1,
+        Version::V2 => {
+            // At least one byte of the payload is always sent
+            while len > 1 && payload[len - 1] == 0 {
+                len -= 1;
+            }
+            HEADER_LEN_V2
+        }
+    };
+
+    let frame = buf
+        .get_mut(..header_len + len + CHECKSUM_LEN)
+        .ok_or(Error::CapacityExceeded)?;
+
+    // The payload length always fits in a byte
+    let len_byte = u8::try_from(len).unwrap_or(u8::MAX);
+    let (sequence, system, component) = (header.sequence, header.system_id, header.component_id);
+    match header.version {
+        Version::V1 => {
+            frame[..HEADER_LEN_V1]
+                .copy_from_slice(&[STX_V1, len_byte, sequence, system, component, info.id]);
+        }
+        Version::V2 => frame[..HEADER_LEN_V2].copy_from_slice(&[
+            STX_V2, len_byte, 0, 0, sequence, system, component, info.id, 0, 0,
+        ]),
+    }
+    frame[header_len..header_len + len].copy_from_slice(&payload[..len]);
+
+    let checksum = checksum(&frame[1..header_len + len], info.crc_extra);
+    frame[header_len + len..].copy_from_slice(&checksum.to_le_bytes());
+
+    Ok(frame.len())
+}
+
+/// Returns the length of the Mavlink frame at the start of the bytes.
+///
+/// This requires the bytes to at least contain the header of the frame,
+/// and can be used to split a stream of bytes into frames.
+#[must_use]
+pub fn frame_len(bytes: &[u8]) -> Option<usize> {
+    let len = usize::from(*bytes.get(1)?);
+    match *bytes.first()? {
+        STX_V1 => Some(HEADER_LEN_V1 + len + CHECKSUM_LEN),
+        STX_V2 => {
+            let signed = bytes.get(2)? & IFLAG_SIGNED != 0;
+            let signature_len = if signed { SIGNATURE_LEN } else { 0 };
+            Some(HEADER_LEN_V2 + len + CHECKSUM_LEN + signature_len)
+        }
+        _ => None,
+    }
+}
+
+/// Decode a Mavlink frame into a message.
+///
+/// The value of `PARAM_SET` and `PARAM_VALUE` is decoded using the given [`Encoding`].
+/// Any bytes after the frame are ignored, and signatures are not verified.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidFrame`] if the bytes do not hold a complete frame,
+/// [`Error::BadChecksum`] if the checksum does not match, [`Error::UnsupportedMessage`]
+/// for messages other than the parameter protocol, and any error from decoding the
+/// identifier or value of the message.
+pub fn decode(bytes: &[u8], encoding: Encoding) -> Result<(Header, Message), Error> {
+    let frame_len = frame_len(bytes).ok_or(Error::InvalidFrame)?;
+    let frame = bytes.get(..frame_len).ok_or(Error::InvalidFrame)?;
+    let len = usize::from(frame[1]);
+
+    // The sequence, system id and component id follow each other at `at`
+    let (header_len, version, at, id) = match frame[0] {
+        STX_V1 => (HEADER_LEN_V1, Version::V1, 2, u32::from(frame[5])),
+        _ if frame[2] & !IFLAG_SIGNED != 0 => return Err(Error::InvalidFrame),
+        _ => {
+            let id = u32::from_le_bytes([frame[7], frame[8], frame[9], 0]);
+            (HEADER_LEN_V2, Version::V2, 4, id)
+        }
+    };
+
+    let info = [
+        PARAM_REQUEST_READ,
+        PARAM_REQUEST_LIST,
+        PARAM_VALUE,
+        PARAM_SET,
+    ]
+    .into_iter()
+    .find(|info| u32::from(info.id) == id)
+    .ok_or(Error::UnsupportedMessage)?;
+
+    // Only version 2 allows truncated payloads, and neither allows extensions of these messages
+    if len > info.len || (version == Version::V1 && len != info.len) {
+        return Err(Error::InvalidFrame);
+    }
+
+    let checksum_at = header_len + len;
+    let received = u16::from_le_bytes([frame[checksum_at], frame[checksum_at + 1]]);
+    if checksum(&frame[1..checksum_at], info.crc_extra) != received {
+        return Err(Error::BadChecksum);
+    }
+
+    // Truncated payloads are extended with zeros
+    let mut payload = [0u8; MAX_PAYLOAD_LEN];
+    payload[..len].copy_from_slice(&frame[header_len..checksum_at]);
+
+    let header = Header {
+        version,
+        sequence: frame[at],
+        system_id: frame[at + 1],
+        component_id: frame[at + 2],
+    };
+
+    let message = Message::read_payload(&info, &payload, encoding)?;
+    Ok((header, message))
+}
+
+impl Message {
+    fn write_payload(
+        &self,
+        payload: &mut [u8; MAX_PAYLOAD_LEN],
+        encoding: Encoding,
+    ) -> Result<MessageInfo, Error> {
+        Ok(match self {
+            Message::Request {
+                target_system,
+                target_component,
+                request: Request::List,
+            } => {
+                payload[..2].copy_from_slice(&[*target_system, *target_component]);
+                PARAM_REQUEST_LIST
+            }
+            Message::Request {
+                target_system,
+                target_component,
+                request:
+                    Request::Read {
+                        param_id,
+                        param_index,
+                    },
+            } => {
+                payload[0..2].copy_from_slice(&param_index.to_le_bytes());
+                payload[2..4].copy_from_slice(&[*target_system, *target_component]);
+                payload[4..20].copy_from_slice(param_id.as_raw());
+                PARAM_REQUEST_READ
+            }
+            Message::Request {
+                target_system,
+                target_component,
+                request: Request::Set { param_id, value },
+            } => {
+                payload[0..4].copy_from_slice(&value.encode(encoding)?.to_le_bytes());
+                payload[4..6].copy_from_slice(&[*target_system, *target_component]);
+                payload[6..22].copy_from_slice(param_id.as_raw());
+                payload[22] = value.param_type() as u8;
+                PARAM_SET
+            }
+            Message::Value(response) => {
+                let value = response.param.value;
+                payload[0..4].copy_from_slice(&value.encode(encoding)?.to_le_bytes());
+                payload[4..6].copy_from_slice(&response.param_count.to_le_bytes());
+                payload[6..8].copy_from_slice(&response.param_index.to_le_bytes());
+                payload[8..24].copy_from_slice(response.param.ident.as_raw());
+                payload[24] = value.param_type() as u8;
+                PARAM_VALUE
+            }
+        })
+    }
+
+    fn read_payload(
+        info: &MessageInfo,
+        payload: &[u8; MAX_PAYLOAD_LEN],
+        encoding: Encoding,
+    ) -> Result<Message, Error> {
+        let float = |at: usize| {
+            f32::from_le_bytes([
+                payload[at],
+                payload[at + 1],
+                payload[at + 2],
+                payload[at + 3],
+            ])
+        };
+        let value = |at: usize, type_at: usize| {
+            Value::from_wire_encoded(float(at), ParamType::try_from(payload[type_at])?, encoding)
+        };
+
+        Ok(match info.id {
+            id if id == PARAM_REQUEST_LIST.id => Message::Request {
+                target_system: payload[0],
+                target_component: payload[1],
+                request: Request::List,
+            },
+            id if id == PARAM_REQUEST_READ.id => Message::Request {
+                target_system: payload[2],
+                target_component: payload[3],
+                request: Request::Read {
+                    param_id: Ident::try_from(&payload[4..20])?,
+                    param_index: i16::from_le_bytes([payload[0], payload[1]]),
+                },
+            },
+            id if id == PARAM_SET.id => Message::Request {
+                target_system: payload[4],
+                target_component: payload[5],
+                request: Request::Set {
+                    param_id: Ident::try_from(&payload[6..22])?,
+                    value: value(0, 22)?,
+                },
+            },
+            _ => Message::Value(Response {
+                param: Parameter {
+                    ident: Ident::try_from(&payload[8..24])?,
+                    value: value(0, 24)?,
+                },
+                param_count: u16::from_le_bytes([payload[4], payload[5]]),
+                param_index: u16::from_le_bytes([payload[6], payload[7]]),
+            }),
+        })
+    }
+}
+
+/// The CRC-16/MCRF4XX checksum of the frame, including the `CRC_EXTRA` of the message.
+fn checksum(bytes: &[u8], crc_extra: u8) -> u16 {
+    bytes
+        .iter()
+        .chain(&[crc_extra])
+        .fold(0xFFFF, |crc, byte| crc_accumulate(crc, *byte))
+}
+
+fn crc_accumulate(crc: u16, byte: u8) -> u16 {
+    let mut tmp = byte ^ crc.to_le_bytes()[0];
+    tmp ^= tmp << 4;
+    let tmp = u16::from(tmp);
+    (crc >> 8) ^ (tmp << 8) ^ (tmp << 3) ^ (tmp >> 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Header, MAX_FRAME_LEN, Message, Version, checksum, decode, encode, frame_len};
+    use crate::server::{Request, Response};
+    use crate::{Encoding, Error, Ident, Parameter, Value};
+
+    const GCS_V1: Header = Header {
+        version: Version::V1,
+        sequence: 0,
+        system_id: 255,
+        component_id: 190,
+    };
+
+    const GCS_V2: Header = Header {
+        version: Version::V2,
+        ..GCS_V1
+    };
+
+    fn request(request: Request) -> Message {
+        Message::Request {
+            target_system: 1,
+            target_component: 1,
+            request,
+        }
+    }
+
+    fn round_trip(header: Header, message: &Message, encoding: Encoding, expected: &[u8]) {
+        let mut buf = [0u8; MAX_FRAME_LEN];
+        let len = encode(&header, message, encoding, &mut buf).unwrap();
+        assert_eq!(&buf[..len], expected);
+        assert_eq!(frame_len(expected), Some(expected.len()));
+        assert_eq!(decode(expected, encoding), Ok((header, message.clone())));
+    }
+
+    #[test]
+    fn crc_check_value() {
+        // The checksum without `CRC_EXTRA` is CRC-16/MCRF4XX
+        assert_eq!(checksum(b"12345678", b'9'), 0x6F91);
+    }
+
+    #[test]
+    fn request_list() {
+        round_trip(
+            GCS_V1,
+            &request(Request::List),
+            Encoding::Bytewise,
+            &[0xFE, 0x02, 0x00, 0xFF, 0xBE, 0x15, 0x01, 0x01, 0x79, 0x37],
+        );
+
+        // The trailing zero of the payload is truncated in version 2
+        let message = Message::Request {
+            target_system: 1,
+            target_component: 0,
+            request: Request::List,
+        };
+        let header = Header {
+            sequence: 1,
+            ..GCS_V2
+        };
+        round_trip(
+            header,
+            &message,
+            Encoding::Bytewise,
+            &[
+                0xFD, 0x01, 0x00, 0x00, 0x01, 0xFF, 0xBE, 0x15, 0x00, 0x00, 0x01, 0xC2, 0xB6,
+            ],
+        );
+    }
+
+    #[test]
+    fn request_read() {
+        let message = request(Request::Read {
+            param_id: Ident::from_str_truncated("mav.timeout"),
+            param_index: -1,
+        });
+        let header = Header {
+            sequence: 2,
+            ..GCS_V2
+        };
+        round_trip(
+            header,
+            &message,
+            Encoding::Bytewise,
+            &[
+                0xFD, 0x0F, 0x00, 0x00, 0x02, 0xFF, 0xBE, 0x14, 0x00, 0x00, 0xFF, 0xFF, 0x01, 0x01,
+                0x6D, 0x61, 0x76, 0x2E, 0x74, 0x69, 0x6D, 0x65, 0x6F, 0x75, 0x74, 0x57, 0x50,
+            ],
+        );
+    }
+
+    #[test]
+    fn set() {
+        let message = request(Request::Set {
+            param_id: Ident::from_str_truncated("mav.timeout"),
+            value: Value::U16(100),
+        });
+        let header = Header {
+            sequence: 3,
+            ..GCS_V1
+        };
+        round_trip(
+            header,
+            &message,
+            Encoding::Bytewise,
+            &[
+                0xFE, 0x17, 0x03, 0xFF, 0xBE, 0x17, 0x64, 0x00, 0x00, 0x00, 0x01, 0x01, 0x6D, 0x61,
+                0x76, 0x2E, 0x74, 0x69, 0x6D, 0x65, 0x6F, 0x75, 0x74, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x03, 0x27, 0x68,
+            ],
+        );
+    }
+
+    #[test]
+    fn value() {
+        let message = Message::Value(Response {
+            param: Parameter {
+                ident: Ident::from_str_truncated("mav.timeout"),
+                value: Value::U16(500),
+            },
+            param_index: 2,
+            param_count: 4,
+        });
+        let header = Header {
+            version: Version::V2,
+            sequence: 7,
+            system_id: 1,
+            component_id: 1,
+        };
+        let frame = [
+            0xFD, 0x19, 0x00, 0x00, 0x07, 0x01, 0x01, 0x16, 0x00, 0x00, 0xF4, 0x01, 0x00, 0x00,
+            0x04, 0x00, 0x02, 0x00, 0x6D, 0x61, 0x76, 0x2E, 0x74, 0x69, 0x6D, 0x65, 0x6F, 0x75,
+            0x74, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xAE, 0x1B,
+        ];
+        round_trip(header, &message, Encoding::Bytewise, &frame);
+
+        // The same frame decodes differently using the numeric encoding
+        assert_eq!(decode(&frame, Encoding::CCast), Err(Error::PrecisionLoss));
+
+        let mut buf = [0u8; MAX_FRAME_LEN];
+        let len = encode(&header, &message, Encoding::CCast, &mut buf).unwrap();
+        assert_eq!(decode(&buf[..len], Encoding::CCast), Ok((header, message)));
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let frame = [0xFE, 0x02, 0x00, 0xFF, 0xBE, 0x15, 0x01, 0x01, 0x79, 0x37];
+        let mut buf = [0u8; 9];
+        assert_eq!(
+            encode(
+                &GCS_V1,
+                &request(Request::List),
+                Encoding::Bytewise,
+                &mut buf
+            ),
+            Err(Error::CapacityExceeded)
+        );
+
+        // Incomplete frame and unknown start byte
+        assert_eq!(
+            decode(&frame[..9], Encoding::Bytewise),
+            Err(Error::InvalidFrame)
+        );
+        assert_eq!(
+            decode(&[0x00; 10], Encoding::Bytewise),
+            Err(Error::InvalidFrame)
+        );
+
+        // Corrupted payload
+        let mut corrupted = frame;
+        corrupted[6] = 0x02;
+        assert_eq!(
+            decode(&corrupted, Encoding::Bytewise),
+            Err(Error::BadChecksum)
+        );
+
+        // A heartbeat is not supported
+        let heartbeat = [
+            0xFE, 0x09, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x51, 0x04,
+            0x03, 0x7D, 0xDD,
+        ];
+        assert_eq!(
+            decode(&heartbeat, Encoding::Bytewise),
+            Err(Error::UnsupportedMessage)
+        );
+
+        // Trailing bytes are ignored
+        let mut stream = [0u8; 12];
+        stream[..10].copy_from_slice(&frame);
+        assert!(decode(&stream, Encoding::Bytewise).is_ok());
+    }
+}